@@ -6,22 +6,24 @@ use openxr as xr;
 
 use glam::{Affine3A, Vec3, Vec3A};
 use idmap::IdMap;
+use schemars::JsonSchema;
 use serde::Deserialize;
 use thiserror::Error;
 
 use crate::{
-    config::AStrSetExt,
-    hid::{get_keymap_wl, get_keymap_x11},
+    config::{AStrMapExt, AStrSet, AStrSetExt},
+    hid::{get_keymap_wl, get_keymap_x11, XkbKeymap},
     overlays::{
-        anchor::create_anchor,
+        anchor::{create_anchor, create_anchor_canvas, ANCHOR_NAME},
+        custom::create_custom,
         keyboard::{create_keyboard, KEYBOARD_NAME},
         screen::WlxClientAlias,
-        watch::{create_watch, WATCH_NAME},
+        watch::{create_watch, create_watch_canvas, WATCH_NAME},
     },
     state::AppState,
 };
 
-use super::overlay::{OverlayData, OverlayID};
+use super::overlay::{OverlayBackendData, OverlayData, OverlayID, RelativeTo, WindowKind};
 
 #[derive(Error, Debug)]
 pub enum BackendError {
@@ -54,6 +56,7 @@ where
 {
     overlays: IdMap<usize, OverlayData<T>>,
     wl: Option<WlxClientAlias>,
+    keymap: Option<XkbKeymap>,
 }
 
 impl<T> OverlayContainer<T>
@@ -107,6 +110,38 @@ where
             app.screens.push(meta);
         }
 
+        Self::insert_builtin(&mut overlays, app, keymap.clone(), &show_screens)?;
+
+        Ok(Self {
+            overlays,
+            wl,
+            keymap,
+        })
+    }
+
+    /// Creates a container with only the built-in overlays (anchor, watch, keyboard).
+    /// Desktop capture is not initialized.
+    #[cfg(feature = "headless")]
+    pub fn new_headless(app: &mut AppState) -> anyhow::Result<Self> {
+        let mut overlays = IdMap::new();
+
+        app.screens.clear();
+        let show_screens = app.session.config.show_screens.clone();
+        Self::insert_builtin(&mut overlays, app, None, &show_screens)?;
+
+        Ok(Self {
+            overlays,
+            wl: None,
+            keymap: None,
+        })
+    }
+
+    fn insert_builtin(
+        overlays: &mut IdMap<usize, OverlayData<T>>,
+        app: &mut AppState,
+        keymap: Option<XkbKeymap>,
+        show_screens: &AStrSet,
+    ) -> anyhow::Result<()> {
         let anchor = create_anchor(app)?;
         overlays.insert(anchor.state.id.0, anchor);
 
@@ -119,7 +154,7 @@ where
         keyboard.state.want_visible = false;
         overlays.insert(keyboard.state.id.0, keyboard);
 
-        Ok(Self { overlays, wl })
+        Ok(())
     }
 
     #[cfg(not(feature = "wayland"))]
@@ -127,10 +162,12 @@ where
         Ok(vec![])
     }
     #[cfg(feature = "wayland")]
-    pub fn update(&mut self, app: &mut AppState) -> anyhow::Result<Vec<OverlayData<T>>> {
-        use crate::overlays::{
-            screen::{create_screen_interaction, create_screen_renderer_wl, load_pw_token_config},
-            watch::create_watch_canvas,
+    pub fn update(&mut self, app: &mut AppState) -> anyhow::Result<Vec<OverlayData<T>>>
+    where
+        T: OverlayBackendData,
+    {
+        use crate::overlays::screen::{
+            create_screen_interaction, create_screen_renderer_wl, load_pw_token_config,
         };
         use glam::vec2;
         use wlx_capture::wayland::OutputChangeEvent;
@@ -250,7 +287,9 @@ where
             let watch = self.mut_by_name(WATCH_NAME).unwrap(); // want panic
             match create_watch_canvas(None, app) {
                 Ok(canvas) => {
-                    watch.backend = Box::new(canvas);
+                    if let Err(e) = watch.replace_backend(app, Box::new(canvas)) {
+                        log::error!("Failed to replace watch canvas: {:?}", e);
+                    }
                 }
                 Err(e) => {
                     log::error!("Failed to create watch canvas: {}", e);
@@ -261,6 +300,20 @@ where
         Ok(removed_overlays)
     }
 
+    /// Rebuilds the keyboard in place, e.g. after keyboard.yaml was changed.
+    pub fn rebuild_keyboard(&mut self, app: &mut AppState) -> anyhow::Result<()>
+    where
+        T: OverlayBackendData,
+    {
+        let new = create_keyboard::<T>(app, self.keymap.clone())?;
+        let Some(keyboard) = self.mut_by_name(KEYBOARD_NAME) else {
+            return Ok(());
+        };
+        keyboard.state.spawn_scale = new.state.spawn_scale;
+        keyboard.state.interaction_transform = new.state.interaction_transform;
+        keyboard.replace_backend(app, new.backend)
+    }
+
     pub fn mut_by_selector(&mut self, selector: &OverlaySelector) -> Option<&mut OverlayData<T>> {
         match selector {
             OverlaySelector::Id(id) => self.mut_by_id(*id),
@@ -310,6 +363,88 @@ where
         self.overlays.insert(overlay.state.id.0, overlay);
     }
 
+    /// Re-applies the config values that overlays copy at creation, e.g. after switching profiles.
+    pub fn apply_config(&mut self, app: &mut AppState)
+    where
+        T: OverlayBackendData,
+    {
+        let config = &app.session.config;
+        let show_screens = config.show_screens.clone();
+        let curve_values = config.curve_values.clone();
+        let transform_values = config.transform_values.clone();
+        let watch_relative_to = RelativeTo::Hand(config.watch_hand as usize);
+        let (watch_pos, watch_rot) = (config.watch_pos, config.watch_rot);
+
+        for o in self.overlays.values_mut() {
+            let name = o.state.name.clone();
+            o.state.curvature = curve_values.arc_get(&name).copied();
+
+            let is_screen = app.screens.iter().any(|s| s.id == o.state.id);
+            if !show_screens.is_empty() && (is_screen || *name == *KEYBOARD_NAME) {
+                o.state.show_hide = show_screens.arc_get(&name);
+            }
+
+            if *name == *WATCH_NAME {
+                o.state.relative_to = watch_relative_to;
+                o.state.spawn_point = watch_pos;
+                o.state.spawn_rotation = watch_rot;
+            } else if matches!(o.state.relative_to, RelativeTo::None) {
+                if let Some(transform) = transform_values.arc_get(&name) {
+                    o.state.saved_transform = Some(*transform);
+                    o.state.reset(app, false);
+                }
+            }
+            o.state.dirty = true;
+        }
+
+        self.rebuild_panels(app);
+    }
+
+    /// Rebuilds the keyboard, watch, anchor and UI windows, e.g. after switching themes.
+    pub fn rebuild_panels(&mut self, app: &mut AppState)
+    where
+        T: OverlayBackendData,
+    {
+        if let Err(e) = self.rebuild_keyboard(app) {
+            log::error!("Failed to rebuild keyboard: {:?}", e);
+        }
+
+        if let Some(watch) = self.mut_by_name(WATCH_NAME) {
+            if let Err(e) = create_watch_canvas(None, app)
+                .and_then(|canvas| watch.replace_backend(app, Box::new(canvas)))
+            {
+                log::error!("Failed to rebuild watch canvas: {:?}", e);
+            }
+        }
+
+        if let Some(anchor) = self.mut_by_name(&ANCHOR_NAME) {
+            if let Err(e) = create_anchor_canvas(app)
+                .and_then(|canvas| anchor.replace_backend(app, Box::new(canvas)))
+            {
+                log::error!("Failed to rebuild anchor canvas: {:?}", e);
+            }
+        }
+
+        let windows: Vec<Arc<str>> = self
+            .overlays
+            .values()
+            .filter(|o| o.state.window_kind == Some(WindowKind::Ui))
+            .map(|o| o.state.name.clone())
+            .collect();
+        for name in windows {
+            let Some((state, backend)) = create_custom(app, name.clone()) else {
+                continue;
+            };
+            if let Some(o) = self.mut_by_name(&name) {
+                o.state.spawn_scale = state.spawn_scale;
+                o.state.interaction_transform = state.interaction_transform;
+                if let Err(e) = o.replace_backend(app, backend) {
+                    log::error!("Failed to rebuild {}: {:?}", name, e);
+                }
+            }
+        }
+    }
+
     pub fn show_hide(&mut self, app: &mut AppState) {
         let any_shown = self
             .overlays
@@ -341,7 +476,7 @@ where
     }
 }
 
-#[derive(Clone, Deserialize, Debug)]
+#[derive(Clone, Deserialize, Debug, JsonSchema)]
 #[serde(untagged)]
 pub enum OverlaySelector {
     Id(OverlayID),
@@ -0,0 +1,457 @@
+use std::{
+    collections::HashSet,
+    ffi::{CString, OsStr},
+    mem::size_of,
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    backend::{
+        common::OverlayContainer,
+        overlay::{ui_transform, OverlayBackendData},
+    },
+    config::{
+        load_custom_ui, try_load_known_yaml, ConfigType, GeneralConfig, SAVED_SETTINGS_FILE,
+        SAVED_STATE_FILE,
+    },
+    config_check,
+    config_io::{self, get_conf_d_path, CONFIG_ROOT_PATH},
+    gui::modular::{modular_canvas, ModularUiConfig},
+    overlays::{
+        anchor::ANCHOR_NAME,
+        custom::{create_custom, SETTINGS_NAME},
+        keyboard::{check_layout, KEYBOARD_NAME},
+        toast::{try_load_toast_template, Toast, ToastTopic},
+        watch::{create_watch_canvas, WATCH_NAME},
+    },
+    state::AppState,
+};
+
+// editors tend to write a file in several steps
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+const MAX_DIAGNOSTIC_TOASTS: usize = 3;
+
+// how often the watch thread checks whether it should stop
+const POLL_TIMEOUT_MS: i32 = 200;
+
+const EVENT_MASK: u32 =
+    libc::IN_CLOSE_WRITE | libc::IN_MOVED_TO | libc::IN_MOVED_FROM | libc::IN_DELETE;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum ConfigFile {
+    General,
+    Keyboard,
+    Watch,
+    Anchor,
+    WayVR,
+    Toast,
+    Custom(Arc<str>),
+}
+
+impl ConfigFile {
+    fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?;
+        // written by us when saving, these only hold what is already live
+        if name == SAVED_SETTINGS_FILE || name == SAVED_STATE_FILE {
+            return None;
+        }
+
+        let parent = path.parent()?;
+        if parent == get_conf_d_path().as_path()
+            || config_io::get_profile_path().is_some_and(|p| p.as_path() == parent)
+        {
+            return Some(ConfigFile::General);
+        }
+
+        match name {
+            "config.yaml" => Some(ConfigFile::General),
+            "keyboard.yaml" => Some(ConfigFile::Keyboard),
+            "watch.yaml" => Some(ConfigFile::Watch),
+            "anchor.yaml" => Some(ConfigFile::Anchor),
+            "wayvr.yaml" => Some(ConfigFile::WayVR),
+            "toast.yaml" => Some(ConfigFile::Toast),
+            _ => name
+                .strip_suffix(".yaml")
+                .map(|stem| ConfigFile::Custom(stem.into())),
+        }
+    }
+}
+
+/// Watches the config directory with inotify and rebuilds overlays when their files change.
+pub struct ConfigWatcher {
+    rx_path: mpsc::Receiver<PathBuf>,
+    tx_path: mpsc::Sender<PathBuf>,
+    pending: HashSet<ConfigFile>,
+    last_event: Instant,
+    fd: Option<i32>,
+    watches: Arc<Mutex<Vec<(i32, PathBuf)>>>,
+    /// The profile whose directory is being watched
+    profile: Option<Arc<str>>,
+    profile_wd: Option<i32>,
+    shutdown: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ConfigWatcher {
+    pub fn new() -> Self {
+        let (tx_path, rx_path) = mpsc::channel();
+        Self {
+            rx_path,
+            tx_path,
+            pending: HashSet::new(),
+            last_event: Instant::now(),
+            fd: None,
+            watches: Arc::new(Mutex::new(vec![])),
+            profile: None,
+            profile_wd: None,
+            shutdown: Arc::new(AtomicBool::new(false)),
+            thread: None,
+        }
+    }
+
+    pub fn run(&mut self) {
+        let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC) };
+        if fd < 0 {
+            log::error!(
+                "Failed to initialize inotify. Config files will not be reloaded: {}",
+                std::io::Error::last_os_error()
+            );
+            return;
+        }
+
+        let mut watches = vec![];
+        for dir in [CONFIG_ROOT_PATH.clone(), get_conf_d_path()] {
+            if let Some(wd) = add_watch(fd, &dir) {
+                watches.push((wd, dir));
+            }
+        }
+
+        if watches.is_empty() {
+            unsafe { libc::close(fd) };
+            return;
+        }
+
+        log::info!(
+            "Watching {} for config changes",
+            CONFIG_ROOT_PATH.to_string_lossy()
+        );
+
+        self.fd = Some(fd);
+        self.watches = Arc::new(Mutex::new(watches));
+        self.update_profile_watch();
+
+        let sender = self.tx_path.clone();
+        let watches = self.watches.clone();
+        let shutdown = self.shutdown.clone();
+        self.thread = Some(std::thread::spawn(move || {
+            watch_thread(fd, watches, shutdown, sender)
+        }));
+    }
+
+    /// Moves the watch over to the directory of the active profile, after switching profiles
+    fn update_profile_watch(&mut self) {
+        let Some(fd) = self.fd else {
+            return;
+        };
+        let profile = config_io::get_active_profile();
+        if profile == self.profile {
+            return;
+        }
+        self.profile = profile;
+
+        let Ok(mut watches) = self.watches.lock() else {
+            return;
+        };
+        if let Some(wd) = self.profile_wd.take() {
+            unsafe { libc::inotify_rm_watch(fd, wd) };
+            watches.retain(|(w, _)| *w != wd);
+        }
+        if let Some(dir) = config_io::get_profile_path() {
+            if let Some(wd) = add_watch(fd, &dir) {
+                watches.push((wd, dir));
+                self.profile_wd = Some(wd);
+            }
+        }
+    }
+
+    pub fn submit_pending<T>(&mut self, app: &mut AppState, overlays: &mut OverlayContainer<T>)
+    where
+        T: OverlayBackendData,
+    {
+        self.update_profile_watch();
+
+        // problems found while loading, at startup or since
+        let diags = config_check::take_reported();
+        for diag in diags.iter().take(MAX_DIAGNOSTIC_TOASTS) {
+            Toast::new(
+                ToastTopic::System,
+                "Config problem".into(),
+                diag.to_string().into(),
+            )
+            .with_timeout(10.)
+            .submit(app);
+        }
+        if diags.len() > MAX_DIAGNOSTIC_TOASTS {
+            Toast::new(
+                ToastTopic::System,
+                format!(
+                    "{} more config problems",
+                    diags.len() - MAX_DIAGNOSTIC_TOASTS
+                )
+                .into(),
+                "See the log for details.".into(),
+            )
+            .with_timeout(10.)
+            .submit(app);
+        }
+
+        for path in self.rx_path.try_iter() {
+            if let Some(file) = ConfigFile::from_path(&path) {
+                self.pending.insert(file);
+                self.last_event = Instant::now();
+            }
+        }
+
+        if self.pending.is_empty() || self.last_event.elapsed() < DEBOUNCE {
+            return;
+        }
+
+        for file in self.pending.drain() {
+            log::info!("Reloading {:?}", file);
+            if let Err(e) = reload(&file, app, overlays) {
+                log::error!("Failed to reload {:?}: {:?}", file, e);
+                Toast::new(
+                    ToastTopic::System,
+                    "Failed to reload config".into(),
+                    e.to_string().into(),
+                )
+                .with_timeout(10.)
+                .submit(app);
+            }
+        }
+    }
+}
+
+impl Drop for ConfigWatcher {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        if let Some(fd) = self.fd.take() {
+            unsafe { libc::close(fd) };
+        }
+    }
+}
+
+fn add_watch(fd: i32, dir: &Path) -> Option<i32> {
+    let c_path = CString::new(dir.as_os_str().as_bytes()).ok()?;
+    let wd = unsafe { libc::inotify_add_watch(fd, c_path.as_ptr(), EVENT_MASK) };
+    if wd < 0 {
+        log::warn!(
+            "Could not watch {}: {}",
+            dir.to_string_lossy(),
+            std::io::Error::last_os_error()
+        );
+        return None;
+    }
+    Some(wd)
+}
+
+/// The fd is owned by ConfigWatcher, which closes it after this returns
+fn watch_thread(
+    fd: i32,
+    watches: Arc<Mutex<Vec<(i32, PathBuf)>>>,
+    shutdown: Arc<AtomicBool>,
+    sender: mpsc::Sender<PathBuf>,
+) {
+    const HEADER_LEN: usize = size_of::<libc::inotify_event>();
+    let mut buf = [0u8; 4096];
+
+    while !shutdown.load(Ordering::Relaxed) {
+        let mut pollfd = libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let ready = unsafe { libc::poll(&mut pollfd, 1, POLL_TIMEOUT_MS) };
+        if ready < 0 {
+            let err = std::io::Error::last_os_error();
+            if err.kind() == std::io::ErrorKind::Interrupted {
+                continue;
+            }
+            log::error!("Config watcher stopped: {}", err);
+            break;
+        }
+        if ready == 0 {
+            continue;
+        }
+
+        let len = unsafe { libc::read(fd, buf.as_mut_ptr() as *mut _, buf.len()) };
+        if len < 0 {
+            let err = std::io::Error::last_os_error();
+            if err.kind() == std::io::ErrorKind::Interrupted {
+                continue;
+            }
+            log::error!("Config watcher stopped: {}", err);
+            break;
+        }
+        let len = len as usize;
+
+        let mut offset = 0;
+        while offset + HEADER_LEN <= len {
+            let event = unsafe {
+                std::ptr::read_unaligned(buf.as_ptr().add(offset) as *const libc::inotify_event)
+            };
+            let name_start = offset + HEADER_LEN;
+            let name_end = (name_start + event.len as usize).min(len);
+            offset = name_end;
+
+            let name = buf[name_start..name_end]
+                .split(|b| *b == 0)
+                .next()
+                .unwrap_or_default();
+            if name.is_empty() {
+                continue;
+            }
+
+            let path = watches.lock().ok().and_then(|watches| {
+                watches
+                    .iter()
+                    .find(|(wd, _)| *wd == event.wd)
+                    .map(|(_, dir)| dir.join(OsStr::from_bytes(name)))
+            });
+            if let Some(path) = path {
+                if sender.send(path).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+fn reload<T>(
+    file: &ConfigFile,
+    app: &mut AppState,
+    overlays: &mut OverlayContainer<T>,
+) -> anyhow::Result<()>
+where
+    T: OverlayBackendData,
+{
+    match file {
+        ConfigFile::General => {
+            let config = GeneralConfig::try_load_from_disk()?;
+            let keyboard_changed = keyboard_changed(&app.session.config, &config);
+            let theme_changed = theme_changed(&app.session.config, &config);
+
+            app.session.config = config;
+            app.session.load_toast_rules();
+
+            if theme_changed {
+                overlays.rebuild_panels(app);
+                return Ok(());
+            }
+            if keyboard_changed {
+                overlays.rebuild_keyboard(app)?;
+            }
+            rebuild_watch(None, app, overlays)
+        }
+        ConfigFile::Keyboard => {
+            check_layout()?;
+            overlays.rebuild_keyboard(app)
+        }
+        ConfigFile::Watch => {
+            let config = try_load_known_yaml::<ModularUiConfig>(ConfigType::Watch)?;
+            rebuild_watch(Some(config), app, overlays)
+        }
+        ConfigFile::Anchor => {
+            let config = try_load_known_yaml::<ModularUiConfig>(ConfigType::Anchor)?;
+            let Some(anchor) = overlays.mut_by_name(&ANCHOR_NAME) else {
+                return Ok(());
+            };
+            anchor.state.spawn_scale = config.width;
+            anchor.replace_backend(app, Box::new(modular_canvas(&config, app)?))
+        }
+        ConfigFile::WayVR => {
+            #[cfg(feature = "wayvr")]
+            {
+                app.session.wayvr_config = crate::config_wayvr::try_load_wayvr()?;
+                rebuild_watch(None, app, overlays)?;
+            }
+            Ok(())
+        }
+        ConfigFile::Toast => match try_load_toast_template() {
+            Ok(template) => {
+                app.session.toast_template = template;
+                Ok(())
+            }
+            Err(e) => {
+                // use the built-in layout until it's fixed
+                app.session.toast_template = None;
+                Err(e)
+            }
+        },
+        ConfigFile::Custom(name) => {
+            if [WATCH_NAME, KEYBOARD_NAME, &**ANCHOR_NAME].contains(&name.as_ref())
+                || overlays.mut_by_name(name).is_none()
+            {
+                return Ok(());
+            }
+
+            // validate first, create_custom falls back to defaults
+            if name.as_ref() == SETTINGS_NAME {
+                try_load_known_yaml::<ModularUiConfig>(ConfigType::Settings)?;
+            } else {
+                load_custom_ui(name)?;
+            }
+
+            let Some((state, backend)) = create_custom(app, name.clone()) else {
+                anyhow::bail!("Could not create {}", name);
+            };
+            let Some(overlay) = overlays.mut_by_name(name) else {
+                return Ok(());
+            };
+            overlay.state.spawn_scale = state.spawn_scale;
+            overlay.state.interaction_transform = state.interaction_transform;
+            overlay.replace_backend(app, backend)
+        }
+    }
+}
+
+fn rebuild_watch<T>(
+    config: Option<ModularUiConfig>,
+    app: &mut AppState,
+    overlays: &mut OverlayContainer<T>,
+) -> anyhow::Result<()>
+where
+    T: OverlayBackendData,
+{
+    let Some(watch) = overlays.mut_by_name(WATCH_NAME) else {
+        return Ok(());
+    };
+    if let Some(config) = config.as_ref() {
+        watch.state.spawn_scale = config.width;
+        watch.state.interaction_transform = ui_transform(&config.size);
+    }
+    watch.replace_backend(app, Box::new(create_watch_canvas(config, app)?))
+}
+
+fn keyboard_changed(old: &GeneralConfig, new: &GeneralConfig) -> bool {
+    #[cfg(feature = "osc")]
+    if old.keyboard_chatbox != new.keyboard_chatbox {
+        return true;
+    }
+    old.keyboard_scale != new.keyboard_scale
+}
+
+fn theme_changed(old: &GeneralConfig, new: &GeneralConfig) -> bool {
+    old.theme != new.theme || old.themes != new.themes || old.palette != new.palette
+}
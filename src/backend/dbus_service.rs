@@ -0,0 +1,286 @@
+use std::{collections::HashMap, ffi::CString, sync::mpsc, time::Duration};
+
+use dbus::{
+    arg::{RefArg, Variant},
+    blocking::{stdintf::org_freedesktop_dbus::RequestNameReply, Connection},
+    channel::{MatchingReceiver, Sender},
+    message::MatchRule,
+    strings::ErrorName,
+    Message,
+};
+
+use crate::{
+    backend::{
+        common::{OverlayContainer, OverlaySelector},
+        ipc::{handle_request, IpcRequest, IpcResponse},
+        overlay::OverlayID,
+    },
+    gui::modular::button::ButtonAction,
+    overlays::toast::MAX_TIMEOUT,
+    state::AppState,
+};
+
+const BUS_NAME: &str = "io.github.wlxoverlay";
+const OBJECT_PATH: &str = "/io/github/wlxoverlay";
+const INTERFACE: &str = "io.github.wlxoverlay.Control";
+
+const INTROSPECTION: &str = r#"<!DOCTYPE node PUBLIC "-//freedesktop//DTD D-BUS Object Introspection 1.0//EN"
+ "http://www.freedesktop.org/standards/dbus/1.0/introspect.dtd">
+<node>
+  <interface name="io.github.wlxoverlay.Control">
+    <method name="ShowOverlay">
+      <arg name="overlay" type="v" direction="in"/>
+    </method>
+    <method name="HideOverlay">
+      <arg name="overlay" type="v" direction="in"/>
+    </method>
+    <method name="ListOverlays">
+      <arg name="overlays" type="a(tsb)" direction="out"/>
+    </method>
+    <method name="RunAction">
+      <arg name="action" type="s" direction="in"/>
+    </method>
+    <method name="Toast">
+      <arg name="title" type="s" direction="in"/>
+      <arg name="body" type="s" direction="in"/>
+      <arg name="seconds" type="d" direction="in"/>
+    </method>
+    <method name="ListWayVRDisplays">
+      <arg name="displays" type="a(suubb)" direction="out"/>
+    </method>
+    <signal name="OverlayVisibilityChanged">
+      <arg name="name" type="s"/>
+      <arg name="visible" type="b"/>
+    </signal>
+  </interface>
+  <interface name="org.freedesktop.DBus.Introspectable">
+    <method name="Introspect">
+      <arg name="xml_data" type="s" direction="out"/>
+    </method>
+  </interface>
+</node>"#;
+
+/// Exposes overlay controls on the session bus, as `io.github.wlxoverlay`.
+pub struct DbusService {
+    connection: Option<Connection>,
+    rx_call: mpsc::Receiver<Message>,
+    tx_call: mpsc::SyncSender<Message>,
+    visibility: HashMap<OverlayID, bool>,
+}
+
+impl DbusService {
+    pub fn new() -> Self {
+        let (tx_call, rx_call) = mpsc::sync_channel(32);
+        Self {
+            connection: None,
+            rx_call,
+            tx_call,
+            visibility: HashMap::new(),
+        }
+    }
+
+    pub fn run(&mut self) {
+        let c = match Connection::new_session() {
+            Ok(c) => c,
+            Err(e) => {
+                log::error!(
+                    "Failed to connect to dbus. Will not provide {}: {:?}",
+                    BUS_NAME,
+                    e
+                );
+                return;
+            }
+        };
+
+        match c.request_name(BUS_NAME, false, true, true) {
+            Ok(RequestNameReply::PrimaryOwner | RequestNameReply::AlreadyOwner) => {}
+            Ok(reply) => {
+                log::error!("Could not acquire dbus name {}: {:?}", BUS_NAME, reply);
+                return;
+            }
+            Err(e) => {
+                log::error!("Failed to acquire dbus name {}: {:?}", BUS_NAME, e);
+                return;
+            }
+        }
+
+        let mut rule = MatchRule::new_method_call();
+        rule.path = Some(OBJECT_PATH.into());
+
+        let sender = self.tx_call.clone();
+        c.start_receive(
+            rule,
+            Box::new(move |msg, _| {
+                if let Err(e) = sender.try_send(msg) {
+                    log::error!("Failed to queue dbus call: {:?}", e);
+                }
+                true
+            }),
+        );
+
+        log::info!("Providing dbus service {}", BUS_NAME);
+        self.connection = Some(c);
+    }
+
+    pub fn submit_pending<T>(&mut self, app: &mut AppState, overlays: &mut OverlayContainer<T>)
+    where
+        T: Default,
+    {
+        let Some(c) = &self.connection else {
+            return;
+        };
+        let _ = c.process(Duration::ZERO);
+
+        for msg in self.rx_call.try_iter() {
+            if msg.no_reply() {
+                let _ = handle_call(&msg, app, overlays);
+                continue;
+            }
+
+            let reply = match handle_call(&msg, app, overlays) {
+                Ok(reply) => reply,
+                Err(e) => {
+                    let name = ErrorName::from("io.github.wlxoverlay.Error");
+                    let text = CString::new(e.to_string()).unwrap_or_default();
+                    msg.error(&name, &text)
+                }
+            };
+            let _ = c.send(reply);
+        }
+
+        for o in overlays.iter() {
+            // overlays seen for the first time only seed the last known state
+            let last = self.visibility.insert(o.state.id, o.state.want_visible);
+            if !last.is_some_and(|v| v != o.state.want_visible) {
+                continue;
+            }
+            if let Ok(signal) =
+                Message::new_signal(OBJECT_PATH, INTERFACE, "OverlayVisibilityChanged")
+            {
+                let _ = c.send(signal.append2(o.state.name.as_ref(), o.state.want_visible));
+            }
+        }
+        self.visibility
+            .retain(|id, _| overlays.iter().any(|o| o.state.id == *id));
+    }
+}
+
+fn handle_call<T>(
+    msg: &Message,
+    app: &mut AppState,
+    overlays: &mut OverlayContainer<T>,
+) -> anyhow::Result<Message>
+where
+    T: Default,
+{
+    let interface = msg.interface();
+    let member = msg.member();
+    let (Some(interface), Some(member)) = (interface.as_deref(), member.as_deref()) else {
+        anyhow::bail!("Missing interface or member");
+    };
+
+    if interface == "org.freedesktop.DBus.Introspectable" && member == "Introspect" {
+        return Ok(msg.method_return().append1(INTROSPECTION));
+    }
+    if interface != INTERFACE {
+        anyhow::bail!("Unknown interface: {}", interface);
+    }
+
+    let request = match member {
+        "ShowOverlay" | "HideOverlay" => IpcRequest::SetVisible {
+            target: read_selector(msg)?,
+            visible: member == "ShowOverlay",
+        },
+        "ListOverlays" => {
+            let list: Vec<(u64, String, bool)> = overlays
+                .iter()
+                .map(|o| {
+                    (
+                        o.state.id.0 as u64,
+                        o.state.name.to_string(),
+                        o.state.want_visible,
+                    )
+                })
+                .collect();
+            return Ok(msg.method_return().append1(list));
+        }
+        "RunAction" => {
+            let action: ButtonAction = serde_yaml::from_str(msg.read1::<&str>()?)?;
+            IpcRequest::Action { action }
+        }
+        "Toast" => {
+            let (title, body, seconds) = msg.read3::<&str, &str, f64>()?;
+            // 0 or less picks the default timeout
+            let seconds = if seconds > 0. {
+                if !seconds.is_finite() || seconds > MAX_TIMEOUT as f64 {
+                    anyhow::bail!("seconds must be between 0 and {}", MAX_TIMEOUT);
+                }
+                Some(seconds as f32)
+            } else if seconds.is_nan() {
+                anyhow::bail!("seconds must be a number");
+            } else {
+                None
+            };
+            IpcRequest::Action {
+                action: ButtonAction::Toast {
+                    message: title.into(),
+                    body: Some(body.into()),
+                    seconds,
+                },
+            }
+        }
+        "ListWayVRDisplays" => {
+            return Ok(msg.method_return().append1(list_wayvr_displays(app)));
+        }
+        _ => anyhow::bail!("Unknown method: {}", member),
+    };
+
+    match handle_request(request, app, overlays) {
+        IpcResponse::Error { message } => anyhow::bail!(message),
+        _ => Ok(msg.method_return()),
+    }
+}
+
+/// The overlay is given by name as a string, or by id as an integer
+fn read_selector(msg: &Message) -> anyhow::Result<OverlaySelector> {
+    let Variant(overlay) = msg.read1::<Variant<Box<dyn RefArg>>>()?;
+    let id = overlay
+        .as_u64()
+        .or_else(|| overlay.as_i64().and_then(|id| u64::try_from(id).ok()));
+    if let Some(name) = overlay.as_str() {
+        Ok(OverlaySelector::Name(name.into()))
+    } else if let Some(id) = id {
+        Ok(OverlaySelector::Id(OverlayID(id as usize)))
+    } else {
+        anyhow::bail!("Overlay must be a name or an id");
+    }
+}
+
+/// name, width, height, active, visible
+type DisplayInfo = (String, u32, u32, bool, bool);
+
+#[cfg(feature = "wayvr")]
+fn list_wayvr_displays(app: &AppState) -> Vec<DisplayInfo> {
+    let mut list: Vec<DisplayInfo> = vec![];
+
+    if let Some(wayvr) = &app.wayvr {
+        let wayvr = wayvr.borrow();
+        for cell in wayvr.state.displays.vec.iter().flatten() {
+            let d = &cell.obj;
+            list.push((d.name.clone(), d.width, d.height, true, d.visible));
+        }
+    }
+
+    for (name, conf) in app.session.wayvr_config.displays.iter() {
+        if !list.iter().any(|d| d.0 == *name) {
+            list.push((name.clone(), conf.width, conf.height, false, false));
+        }
+    }
+
+    list
+}
+
+#[cfg(not(feature = "wayvr"))]
+fn list_wayvr_displays(_app: &AppState) -> Vec<DisplayInfo> {
+    vec![]
+}
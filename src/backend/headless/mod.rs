@@ -0,0 +1,267 @@
+use std::{
+    collections::VecDeque,
+    ops::Add,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{
+    backend::{
+        common::{BackendError, OverlayContainer},
+        input::interact,
+        input_record::InputSession,
+        layouts,
+        overlay::{OverlayBackendData, OverlayData},
+        task::{SystemTask, TaskType},
+    },
+    graphics::WlxGraphics,
+    hid::USE_UINPUT,
+    overlays::watch::{watch_fade, WATCH_NAME},
+    state::AppState,
+};
+
+#[cfg(feature = "wayvr")]
+use crate::overlays::wayvr::wayvr_action;
+
+use self::script::{HeadlessScript, ScriptPlayer};
+
+pub mod script;
+
+static FRAME_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Default)]
+pub struct HeadlessOverlayData {
+    init: bool,
+}
+
+impl OverlayBackendData for HeadlessOverlayData {
+    fn initialized(&self) -> bool {
+        self.init
+    }
+    fn resumed(&self) -> bool {
+        false
+    }
+    fn backend_replaced(&mut self) {}
+}
+
+pub fn headless_run(
+    running: Arc<AtomicBool>,
+    script_path: &Path,
+    show_by_default: bool,
+    input_session: &mut InputSession,
+) -> Result<(), BackendError> {
+    let script = HeadlessScript::load(script_path).map_err(|e| {
+        BackendError::Fatal(anyhow::anyhow!(
+            "Could not load headless script {}: {}",
+            script_path.to_string_lossy(),
+            e
+        ))
+    })?;
+
+    log::info!("Using headless backend");
+
+    // never send input to the real desktop
+    USE_UINPUT.store(false, Ordering::Relaxed);
+
+    let mut app_state = {
+        let graphics = WlxGraphics::new_headless()?;
+        AppState::from_graphics(graphics)?
+    };
+
+    if show_by_default {
+        app_state.tasks.enqueue_at(
+            TaskType::System(SystemTask::ShowHide),
+            Instant::now().add(Duration::from_secs(1)),
+        )
+    }
+
+    let mut overlays = OverlayContainer::<HeadlessOverlayData>::new_headless(&mut app_state)?;
+
+    // no socket, bus name or config watcher, so that a running instance is not disturbed
+
+    let watch_id = overlays.get_by_name(WATCH_NAME).unwrap().state.id; // want panic
+
+    let mut player = ScriptPlayer::new(script);
+    let frame_time = Duration::from_secs_f32(1. / player.fps());
+
+    let mut due_tasks = VecDeque::with_capacity(4);
+
+    'main_loop: loop {
+        let frame_start = Instant::now();
+        let cur_frame = FRAME_COUNTER.fetch_add(1, Ordering::Relaxed);
+
+        if !running.load(Ordering::Relaxed) {
+            log::warn!("Received shutdown signal.");
+            break 'main_loop;
+        }
+
+        app_state.input_state.pre_update();
+        if !player.advance(&mut app_state.input_state) {
+            log::info!("Script finished after {} frames.", cur_frame);
+            break 'main_loop;
+        }
+        if !input_session.update(&mut app_state.input_state) {
+            log::info!("Replay finished after {} frames.", cur_frame);
+            break 'main_loop;
+        }
+        app_state.input_state.post_update(&app_state.session);
+
+        if app_state
+            .input_state
+            .pointers
+            .iter()
+            .any(|p| p.now.show_hide && !p.before.show_hide)
+        {
+            overlays.show_hide(&mut app_state);
+        }
+
+        overlays
+            .iter_mut()
+            .for_each(|o| o.state.auto_movement(&mut app_state));
+
+        watch_fade(&mut app_state, overlays.mut_by_id(watch_id).unwrap()); // want panic
+
+        let lengths_haptics = interact(&mut overlays, &mut app_state);
+        for (idx, (len, haptics)) in lengths_haptics.iter().enumerate() {
+            if haptics.is_some() {
+                log::trace!("Hand {}: haptics at {:.3}m", idx, len);
+            }
+        }
+
+        app_state.hid_provider.commit();
+
+        #[cfg(feature = "wayvr")]
+        crate::overlays::wayvr::tick_events::<HeadlessOverlayData>(&mut app_state, &mut overlays)?;
+
+        for o in overlays.iter_mut() {
+            if !o.state.want_visible {
+                continue;
+            }
+
+            if !o.data.init {
+                o.init(&mut app_state)?;
+                o.data.init = true;
+            }
+
+            o.render(&mut app_state)?;
+        }
+
+        #[cfg(feature = "wayvr")]
+        if let Some(wayvr) = &app_state.wayvr {
+            wayvr.borrow_mut().state.tick_finish()?;
+        }
+
+        let _ = overlays.update(&mut app_state)?;
+
+        app_state.tasks.retrieve_due(&mut due_tasks);
+        while let Some(task) = due_tasks.pop_front() {
+            match task {
+                TaskType::Global(f) => f(&mut app_state),
+                TaskType::Overlay(sel, f) => {
+                    if let Some(o) = overlays.mut_by_selector(&sel) {
+                        f(&mut app_state, &mut o.state);
+                    } else {
+                        log::warn!("Overlay not found for task: {:?}", sel);
+                    }
+                }
+                TaskType::CreateOverlay(sel, f) => {
+                    let None = overlays.mut_by_selector(&sel) else {
+                        continue;
+                    };
+
+                    let Some((mut state, backend)) = f(&mut app_state) else {
+                        continue;
+                    };
+                    state.birthframe = cur_frame;
+
+                    overlays.add(OverlayData {
+                        state,
+                        backend,
+                        ..Default::default()
+                    });
+                }
+                TaskType::DropOverlay(sel) => {
+                    if let Some(o) = overlays.mut_by_selector(&sel) {
+                        if o.state.birthframe < cur_frame {
+                            overlays.remove_by_selector(&sel);
+                        }
+                    }
+                }
+                TaskType::System(task) => match task {
+                    SystemTask::ShowHide => {
+                        overlays.show_hide(&mut app_state);
+                    }
+                    SystemTask::ApplyConfig => {
+                        overlays.apply_config(&mut app_state);
+                    }
+                    SystemTask::SaveLayout(name) => {
+                        layouts::save_layout(&name, &mut app_state, &overlays);
+                    }
+                    SystemTask::RestoreLayout(name) => {
+                        layouts::restore_layout(&name, &mut app_state, &mut overlays);
+                    }
+                    SystemTask::ApplyTheme => {
+                        overlays.rebuild_panels(&mut app_state);
+                    }
+                    SystemTask::RebuildKeyboard => {
+                        if let Err(e) = overlays.rebuild_keyboard(&mut app_state) {
+                            log::error!("Failed to rebuild keyboard: {:?}", e);
+                        }
+                    }
+                    SystemTask::ColorGain(..)
+                    | SystemTask::SetColorGain(..)
+                    | SystemTask::FixFloor
+                    | SystemTask::ResetPlayspace => {
+                        log::info!("Headless: ignoring playspace/color task");
+                    }
+                },
+                #[cfg(feature = "wayvr")]
+                TaskType::WayVR(action) => {
+                    wayvr_action(&mut app_state, &mut overlays, &action);
+                }
+            }
+        }
+
+        if let Some(remaining) = frame_time.checked_sub(frame_start.elapsed()) {
+            std::thread::sleep(remaining);
+        }
+    }
+
+    for o in overlays.iter() {
+        let translation = o.state.transform.translation;
+        log::info!(
+            "{}: visible {}, position [{:.3}, {:.3}, {:.3}], scale {:.3}, curvature {:?}",
+            o.state.name,
+            o.state.want_visible,
+            translation.x,
+            translation.y,
+            translation.z,
+            o.state.transform.x_axis.length(),
+            o.state.curvature,
+        );
+    }
+
+    let failed = player.script().check(
+        overlays
+            .iter()
+            .map(|o| (o.state.name.as_ref(), o.state.want_visible)),
+    );
+    for message in failed.iter() {
+        log::error!("Expectation failed: {}", message);
+    }
+
+    log::warn!("Headless shutdown");
+
+    if !failed.is_empty() {
+        return Err(BackendError::Fatal(anyhow::anyhow!(
+            "{} expectation(s) failed",
+            failed.len()
+        )));
+    }
+
+    Ok(())
+}
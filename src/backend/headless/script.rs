@@ -0,0 +1,239 @@
+use std::{path::Path, sync::Arc};
+
+use glam::{Affine3A, Quat, Vec3};
+use serde::Deserialize;
+
+use crate::backend::input::{InputState, PointerState};
+
+fn def_fps() -> f32 {
+    60.
+}
+
+fn def_one() -> usize {
+    1
+}
+
+#[derive(Deserialize)]
+pub struct HeadlessScript {
+    /// Simulated frames per second
+    #[serde(default = "def_fps")]
+    pub fps: f32,
+    pub steps: Vec<ScriptStep>,
+    /// Overlay states to check once the script has finished
+    #[serde(default)]
+    pub expect: Vec<ScriptExpect>,
+}
+
+impl HeadlessScript {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let data = std::fs::read_to_string(path)?;
+        let script: HeadlessScript = serde_yaml::from_str(&data)?;
+        if !script.fps.is_normal() || script.fps < 0. {
+            anyhow::bail!("fps must be a positive number");
+        }
+        Ok(script)
+    }
+
+    /// Takes the name and visibility of each overlay.
+    /// Returns a message for every expectation that was not met.
+    pub fn check<'a>(
+        &self,
+        overlays: impl Iterator<Item = (&'a str, bool)> + Clone,
+    ) -> Vec<String> {
+        let mut failed = vec![];
+        for expect in &self.expect {
+            let Some((_, visible)) = overlays.clone().find(|(name, _)| **name == *expect.overlay)
+            else {
+                failed.push(format!("{}: overlay not found", expect.overlay));
+                continue;
+            };
+            if let Some(want) = expect.visible.filter(|v| *v != visible) {
+                failed.push(format!(
+                    "{}: expected visible {}, got {}",
+                    expect.overlay, want, visible
+                ));
+            }
+        }
+        failed
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ScriptExpect {
+    pub overlay: Arc<str>,
+    pub visible: Option<bool>,
+}
+
+/// Poses and pointer states are held for the duration of the step.
+/// Fields that are left out keep their value from the previous step.
+#[derive(Deserialize)]
+pub struct ScriptStep {
+    #[serde(default = "def_one")]
+    pub frames: usize,
+    /// Move poses gradually from the previous step, instead of jumping on the first frame
+    #[serde(default)]
+    pub interpolate: bool,
+    pub hmd: Option<ScriptPose>,
+    pub left: Option<ScriptPointer>,
+    pub right: Option<ScriptPointer>,
+}
+
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(default)]
+pub struct ScriptPose {
+    pub pos: Vec3,
+    pub rot: Quat,
+}
+
+impl ScriptPose {
+    fn from_affine(affine: &Affine3A) -> Self {
+        let (_, rot, pos) = affine.to_scale_rotation_translation();
+        Self { pos, rot }
+    }
+
+    fn lerp(&self, other: &Self, t: f32) -> Affine3A {
+        Affine3A::from_rotation_translation(
+            self.rot.slerp(other.rot, t),
+            self.pos.lerp(other.pos, t),
+        )
+    }
+}
+
+#[derive(Deserialize, Clone, Copy)]
+pub struct ScriptPointer {
+    #[serde(flatten)]
+    pub pose: ScriptPose,
+    #[serde(flatten)]
+    pub state: PointerState,
+}
+
+pub struct ScriptPlayer {
+    script: HeadlessScript,
+    step_idx: usize,
+    step_frame: usize,
+    start_hmd: ScriptPose,
+    start_pointers: [ScriptPose; 2],
+}
+
+impl ScriptPlayer {
+    pub fn new(script: HeadlessScript) -> Self {
+        Self {
+            script,
+            step_idx: 0,
+            step_frame: 0,
+            start_hmd: ScriptPose::default(),
+            start_pointers: [ScriptPose::default(); 2],
+        }
+    }
+
+    pub fn fps(&self) -> f32 {
+        self.script.fps
+    }
+
+    pub fn script(&self) -> &HeadlessScript {
+        &self.script
+    }
+
+    /// Applies the next frame of the script to the input state.
+    /// Returns false once the script has run out of steps.
+    pub fn advance(&mut self, input: &mut InputState) -> bool {
+        loop {
+            let Some(step) = self.script.steps.get(self.step_idx) else {
+                return false;
+            };
+            if self.step_frame < step.frames.max(1) {
+                break;
+            }
+            self.step_idx += 1;
+            self.step_frame = 0;
+        }
+
+        if self.step_frame == 0 {
+            self.start_hmd = ScriptPose::from_affine(&input.hmd);
+            for (i, p) in input.pointers.iter().enumerate() {
+                self.start_pointers[i] = ScriptPose::from_affine(&p.pose);
+            }
+        }
+
+        let step = &self.script.steps[self.step_idx]; // safe, checked above
+        let t = if step.interpolate {
+            (self.step_frame + 1) as f32 / step.frames.max(1) as f32
+        } else {
+            1.0
+        };
+
+        if let Some(hmd) = step.hmd.as_ref() {
+            input.hmd = self.start_hmd.lerp(hmd, t);
+        }
+
+        for (i, pointer) in [step.left.as_ref(), step.right.as_ref()]
+            .into_iter()
+            .enumerate()
+        {
+            if let Some(pointer) = pointer {
+                let pose = self.start_pointers[i].lerp(&pointer.pose, t);
+                input.pointers[i].pose = pose;
+                input.pointers[i].raw_pose = pose;
+                input.pointers[i].now = pointer.state;
+            }
+        }
+
+        self.step_frame += 1;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCRIPT: &str = r#"
+steps:
+  - frames: 2
+    hmd: { pos: [0, 1.6, 0] }
+    left: { pos: [-0.2, 1.3, -0.3], show_hide: true }
+  - frames: 4
+    interpolate: true
+    left: { pos: [0.2, 1.3, -0.3] }
+expect:
+  - { overlay: watch, visible: true }
+  - { overlay: kbd, visible: false }
+"#;
+
+    #[test]
+    fn player_runs_steps_in_order() {
+        let script: HeadlessScript = serde_yaml::from_str(SCRIPT).unwrap();
+        let mut player = ScriptPlayer::new(script);
+        let mut input = InputState::new();
+
+        for _ in 0..2 {
+            assert!(player.advance(&mut input));
+            assert!(input.pointers[0].now.show_hide);
+            assert_eq!(input.hmd.translation, Vec3::new(0., 1.6, 0.).into());
+        }
+
+        assert!(player.advance(&mut input));
+        assert!(!input.pointers[0].now.show_hide);
+        assert!((input.pointers[0].pose.translation.x + 0.1).abs() < 1e-5);
+
+        for _ in 0..3 {
+            assert!(player.advance(&mut input));
+        }
+        assert!((input.pointers[0].pose.translation.x - 0.2).abs() < 1e-5);
+        assert!(!player.advance(&mut input));
+    }
+
+    #[test]
+    fn expectations_are_checked() {
+        let script: HeadlessScript = serde_yaml::from_str(SCRIPT).unwrap();
+
+        let overlays = [("watch", true), ("kbd", false)];
+        assert!(script.check(overlays.into_iter()).is_empty());
+
+        let overlays = [("watch", true), ("kbd", true)];
+        assert_eq!(script.check(overlays.into_iter()).len(), 1);
+
+        let overlays = [("kbd", false)];
+        assert_eq!(script.check(overlays.into_iter()).len(), 1);
+    }
+}
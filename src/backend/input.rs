@@ -3,6 +3,7 @@ use std::process::{Child, Command};
 use std::{collections::VecDeque, time::Instant};
 
 use glam::{Affine3A, Vec2, Vec3, Vec3A, Vec3Swizzles};
+use serde::{Deserialize, Serialize};
 
 use smallvec::{smallvec, SmallVec};
 
@@ -208,7 +209,8 @@ impl Pointer {
     }
 }
 
-#[derive(Clone, Copy, Default)]
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct PointerState {
     pub scroll: f32,
     pub click: bool,
@@ -520,6 +522,8 @@ impl Pointer {
             old_curvature: overlay.state.curvature,
             grab_all: matches!(self.interaction.mode, PointerMode::Right),
         });
+        // the hand takes over from a running reset or layout animation
+        overlay.state.move_animation = None;
         tasks.enqueue(TaskType::Overlay(
             OverlaySelector::Name(ANCHOR_NAME.clone()),
             Box::new(|app, o| {
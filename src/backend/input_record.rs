@@ -0,0 +1,176 @@
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use glam::Affine3A;
+use serde::{Deserialize, Serialize};
+
+use super::input::{InputState, PointerState};
+
+/// A single frame of input, stored as one JSON object per line.
+#[derive(Serialize, Deserialize)]
+struct InputFrame {
+    hmd: Affine3A,
+    pointers: [RecordedPointer; 2],
+}
+
+#[derive(Serialize, Deserialize)]
+struct RecordedPointer {
+    pose: Affine3A,
+    raw_pose: Affine3A,
+    #[serde(default)]
+    state: PointerState,
+}
+
+impl InputFrame {
+    fn capture(input: &InputState) -> Self {
+        let pointer = |idx: usize| RecordedPointer {
+            pose: input.pointers[idx].pose,
+            raw_pose: input.pointers[idx].raw_pose,
+            state: input.pointers[idx].now,
+        };
+
+        Self {
+            hmd: input.hmd,
+            pointers: [pointer(0), pointer(1)],
+        }
+    }
+
+    fn apply(&self, input: &mut InputState) {
+        input.hmd = self.hmd;
+        for (pointer, recorded) in input.pointers.iter_mut().zip(self.pointers.iter()) {
+            pointer.pose = recorded.pose;
+            pointer.raw_pose = recorded.raw_pose;
+            pointer.now = recorded.state;
+        }
+    }
+}
+
+/// Records live input to a file, or replays a previous recording in place of live input.
+#[derive(Default)]
+pub struct InputSession {
+    recorder: Option<BufWriter<File>>,
+    replay: Option<VecDeque<InputFrame>>,
+}
+
+impl InputSession {
+    pub fn new(record_path: Option<&Path>, replay_path: Option<&Path>) -> anyhow::Result<Self> {
+        let mut session = Self::default();
+
+        if let Some(path) = replay_path {
+            let reader = BufReader::new(File::open(path)?);
+            let mut frames = VecDeque::new();
+            for (idx, line) in reader.lines().enumerate() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let frame = serde_json::from_str(&line)
+                    .map_err(|e| anyhow::anyhow!("line {}: {}", idx + 1, e))?;
+                frames.push_back(frame);
+            }
+            log::info!(
+                "Replaying {} frames of input from {}",
+                frames.len(),
+                path.to_string_lossy()
+            );
+            session.replay = Some(frames);
+        }
+
+        if let Some(path) = record_path {
+            log::info!("Recording input to {}", path.to_string_lossy());
+            session.recorder = Some(BufWriter::new(File::create(path)?));
+        }
+
+        Ok(session)
+    }
+
+    /// Call after the input source has been polled, before post_update.
+    /// Returns false on the frame a replay runs out, after which live input is used again.
+    pub fn update(&mut self, input: &mut InputState) -> bool {
+        let mut ok = true;
+
+        if let Some(frames) = self.replay.as_mut() {
+            if let Some(frame) = frames.pop_front() {
+                frame.apply(input);
+            } else {
+                log::info!("Input replay finished.");
+                self.replay = None;
+                ok = false;
+            }
+        }
+
+        if let Some(writer) = self.recorder.as_mut() {
+            let result = serde_json::to_writer(&mut *writer, &InputFrame::capture(input))
+                .map_err(anyhow::Error::from)
+                .and_then(|_| writer.write_all(b"\n").map_err(anyhow::Error::from));
+            if let Err(e) = result {
+                log::error!("Failed to record input, stopping recording: {}", e);
+                self.recorder = None;
+            }
+        }
+
+        ok
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Quat, Vec3};
+
+    use super::*;
+
+    fn simulate(input: &mut InputState, frame: usize) {
+        let t = frame as f32 * 0.1;
+        input.hmd =
+            Affine3A::from_rotation_translation(Quat::from_rotation_y(t), Vec3::new(0., 1.6, t));
+        for (idx, pointer) in input.pointers.iter_mut().enumerate() {
+            pointer.raw_pose = Affine3A::from_translation(Vec3::new(idx as f32, t, -0.3));
+            pointer.pose = pointer.raw_pose * Affine3A::from_rotation_x(t);
+            pointer.now = PointerState {
+                click: frame % 3 == idx,
+                grab: frame > 5,
+                scroll: t * 0.5,
+                ..Default::default()
+            };
+        }
+    }
+
+    #[test]
+    fn replay_matches_recording() {
+        const FRAMES: usize = 10;
+        let path = std::env::temp_dir().join(format!(
+            "wlx-input-record-test-{}.jsonl",
+            std::process::id()
+        ));
+
+        let mut recorded = vec![];
+        {
+            let mut session = InputSession::new(Some(&path), None).unwrap();
+            let mut input = InputState::new();
+            for frame in 0..FRAMES {
+                simulate(&mut input, frame);
+                assert!(session.update(&mut input));
+                recorded.push(InputFrame::capture(&input));
+            }
+        }
+
+        let mut session = InputSession::new(None, Some(&path)).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        let mut input = InputState::new();
+        for frame in recorded.iter() {
+            assert!(session.update(&mut input));
+            assert_eq!(input.hmd, frame.hmd);
+            for (pointer, recorded) in input.pointers.iter().zip(frame.pointers.iter()) {
+                assert_eq!(pointer.pose, recorded.pose);
+                assert_eq!(pointer.raw_pose, recorded.raw_pose);
+                assert!(pointer.now == recorded.state);
+            }
+        }
+        assert!(!session.update(&mut input));
+    }
+}
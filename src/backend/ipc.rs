@@ -0,0 +1,323 @@
+use std::{
+    fs::Permissions,
+    io::{BufRead, BufReader, Write},
+    os::unix::{
+        fs::PermissionsExt,
+        io::AsRawFd,
+        net::{UnixListener, UnixStream},
+    },
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    time::Duration,
+};
+
+use glam::{Quat, Vec3};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    backend::{
+        common::{OverlayContainer, OverlaySelector},
+        task::{SystemTask, TaskType},
+    },
+    gui::modular::button::{run_button_action, ButtonAction},
+    overlays::toast::{is_valid_timeout, MAX_TIMEOUT},
+    state::AppState,
+};
+
+const SOCKET_NAME: &str = "wlx-overlay-s.sock";
+
+/// Only XDG_RUNTIME_DIR is used, since it is private to the user
+pub fn socket_path() -> anyhow::Result<PathBuf> {
+    let Some(dir) = std::env::var_os("XDG_RUNTIME_DIR") else {
+        anyhow::bail!("XDG_RUNTIME_DIR is not set");
+    };
+    Ok(PathBuf::from(dir).join(SOCKET_NAME))
+}
+
+/// One JSON object per line is read from the socket, and one response line is written back.
+#[derive(Deserialize, Clone)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum IpcRequest {
+    /// List all overlays with their visibility and transform
+    ListOverlays,
+    /// Show or hide a single overlay
+    SetVisible {
+        target: OverlaySelector,
+        visible: bool,
+    },
+    /// Run a single action, as if a button was pressed
+    Action {
+        action: ButtonAction,
+    },
+    /// Toggle the working set, same as the Show/Hide binding
+    ShowHide,
+    ResetPlayspace,
+    FixFloor,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct OverlayInfo {
+    pub id: usize,
+    pub name: Arc<str>,
+    pub visible: bool,
+    pub position: Vec3,
+    pub rotation: Quat,
+    pub scale: f32,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum IpcResponse {
+    Ok,
+    Overlays { overlays: Vec<OverlayInfo> },
+    Error { message: String },
+}
+
+pub fn handle_request<T>(
+    request: IpcRequest,
+    app: &mut AppState,
+    overlays: &mut OverlayContainer<T>,
+) -> IpcResponse
+where
+    T: Default,
+{
+    match request {
+        IpcRequest::ListOverlays => {
+            let overlays = overlays
+                .iter()
+                .map(|o| {
+                    let (scale, rotation, position) =
+                        o.state.transform.to_scale_rotation_translation();
+                    OverlayInfo {
+                        id: o.state.id.0,
+                        name: o.state.name.clone(),
+                        visible: o.state.want_visible,
+                        position,
+                        rotation,
+                        scale: scale.x,
+                    }
+                })
+                .collect();
+            return IpcResponse::Overlays { overlays };
+        }
+        IpcRequest::SetVisible { target, visible } => {
+            if overlays.mut_by_selector(&target).is_none() {
+                return IpcResponse::Error {
+                    message: format!("Overlay not found: {:?}", target),
+                };
+            }
+            app.tasks.enqueue(TaskType::Overlay(
+                target,
+                Box::new(move |app, o| {
+                    if o.want_visible == visible {
+                        return;
+                    }
+                    o.want_visible = visible;
+                    if o.recenter {
+                        o.show_hide = visible;
+                        o.reset(app, false);
+                    }
+                }),
+            ));
+        }
+        IpcRequest::Action { action } => {
+            if matches!(action, ButtonAction::Exec { .. }) && !app.session.config.ipc_allow_exec {
+                return IpcResponse::Error {
+                    message: "Exec actions are disabled, see ipc_allow_exec in config.yaml".into(),
+                };
+            }
+            if let ButtonAction::Toast {
+                seconds: Some(seconds),
+                ..
+            } = &action
+            {
+                if !is_valid_timeout(*seconds) {
+                    return IpcResponse::Error {
+                        message: format!("seconds must be between 0 and {}", MAX_TIMEOUT),
+                    };
+                }
+            }
+            run_button_action(&action, app);
+        }
+        IpcRequest::ShowHide => app.tasks.enqueue(TaskType::System(SystemTask::ShowHide)),
+        IpcRequest::ResetPlayspace => app
+            .tasks
+            .enqueue(TaskType::System(SystemTask::ResetPlayspace)),
+        IpcRequest::FixFloor => app.tasks.enqueue(TaskType::System(SystemTask::FixFloor)),
+    }
+    IpcResponse::Ok
+}
+
+type PendingRequest = (IpcRequest, mpsc::Sender<IpcResponse>);
+
+pub struct IpcServer {
+    rx_request: mpsc::Receiver<PendingRequest>,
+    tx_request: mpsc::SyncSender<PendingRequest>,
+    running: Arc<AtomicBool>,
+    path: Option<PathBuf>,
+}
+
+impl IpcServer {
+    pub fn new() -> Self {
+        let (tx_request, rx_request) = mpsc::sync_channel(10);
+        Self {
+            rx_request,
+            tx_request,
+            running: Arc::new(AtomicBool::new(true)),
+            path: None,
+        }
+    }
+
+    pub fn submit_pending<T>(&self, app: &mut AppState, overlays: &mut OverlayContainer<T>)
+    where
+        T: Default,
+    {
+        self.rx_request.try_iter().for_each(|(request, reply)| {
+            let _ = reply.send(handle_request(request, app, overlays));
+        });
+    }
+
+    pub fn run(&mut self) {
+        let path = match socket_path() {
+            Ok(path) => path,
+            Err(e) => {
+                log::error!("Will not listen for commands: {}", e);
+                return;
+            }
+        };
+
+        if UnixStream::connect(&path).is_ok() {
+            log::error!(
+                "Control socket {} is in use by another instance, will not listen.",
+                path.to_string_lossy()
+            );
+            return;
+        }
+        let _ = std::fs::remove_file(&path);
+
+        let listener = match UnixListener::bind(&path) {
+            Ok(l) => l,
+            Err(e) => {
+                log::error!(
+                    "Failed to bind control socket @ {}: {:?}",
+                    path.to_string_lossy(),
+                    e
+                );
+                return;
+            }
+        };
+        if let Err(e) = std::fs::set_permissions(&path, Permissions::from_mode(0o600)) {
+            log::error!("Failed to set control socket permissions: {:?}", e);
+            let _ = std::fs::remove_file(&path);
+            return;
+        }
+        if let Err(e) = listener.set_nonblocking(true) {
+            log::error!("Failed to set control socket non-blocking: {:?}", e);
+            return;
+        }
+        log::info!("Listening for commands on {}", path.to_string_lossy());
+        self.path = Some(path);
+
+        let sender = self.tx_request.clone();
+        let running = self.running.clone();
+        let _ = std::thread::spawn(move || {
+            while running.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        let uid = unsafe { libc::getuid() };
+                        if peer_uid(&stream) != Some(uid) {
+                            log::warn!("Rejected control socket client of another user");
+                            continue;
+                        }
+                        let sender = sender.clone();
+                        let _ = std::thread::spawn(move || handle_client(stream, sender));
+                    }
+                    Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                        std::thread::sleep(Duration::from_millis(100));
+                    }
+                    Err(e) => {
+                        log::error!("Control socket error: {:?}", e);
+                        break;
+                    }
+                }
+            }
+            log::info!("Control socket listener stopped.");
+        });
+    }
+}
+
+impl Drop for IpcServer {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(path) = self.path.take() {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// Uid of the connecting process, as reported by the kernel
+fn peer_uid(stream: &UnixStream) -> Option<u32> {
+    let mut cred = libc::ucred {
+        pid: 0,
+        uid: 0,
+        gid: 0,
+    };
+    let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+    let ret = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut cred as *mut libc::ucred as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    (ret == 0).then_some(cred.uid)
+}
+
+fn handle_client(stream: UnixStream, sender: mpsc::SyncSender<PendingRequest>) {
+    let _ = stream.set_nonblocking(false);
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str::<IpcRequest>(&line) {
+            Ok(request) => {
+                let (tx_reply, rx_reply) = mpsc::channel();
+                if sender.try_send((request, tx_reply)).is_err() {
+                    IpcResponse::Error {
+                        message: "Too many pending requests".into(),
+                    }
+                } else {
+                    rx_reply
+                        .recv_timeout(Duration::from_secs(5))
+                        .unwrap_or_else(|_| IpcResponse::Error {
+                            message: "Timed out waiting for a response".into(),
+                        })
+                }
+            }
+            Err(e) => IpcResponse::Error {
+                message: format!("Invalid request: {}", e),
+            },
+        };
+
+        let Ok(mut out) = serde_json::to_string(&response) else {
+            break;
+        };
+        out.push('\n');
+        if writer.write_all(out.as_bytes()).is_err() {
+            break;
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use std::{path::PathBuf, sync::Arc};
+
+use glam::Affine3A;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    backend::{
+        common::OverlayContainer,
+        overlay::{RelativeTo, WindowKind},
+    },
+    config::{AStrMap, AStrMapExt, AStrSet, AStrSetExt},
+    config_io::CONFIG_ROOT_PATH,
+    gui::modular::button::{run_button_action, ButtonAction, WindowAction},
+    overlays::toast::{Toast, ToastTopic},
+    state::AppState,
+};
+
+/// A named snapshot of where overlays are, saved as `layouts/<name>.json5`
+#[derive(Default, Serialize, Deserialize)]
+pub struct SavedLayout {
+    #[serde(default)]
+    pub show_screens: AStrSet,
+    #[serde(default)]
+    pub curve_values: AStrMap<f32>,
+    #[serde(default)]
+    pub transform_values: AStrMap<Affine3A>,
+    /// Custom panels and mirrors that were open
+    #[serde(default)]
+    pub windows: AStrMap<WindowKind>,
+}
+
+fn layout_path(name: &str) -> anyhow::Result<PathBuf> {
+    // must stay inside of the layouts directory
+    if name.is_empty() || name.contains(['/', '\\']) || name.contains("..") {
+        anyhow::bail!("Invalid layout name: {:?}", name);
+    }
+    let mut path = CONFIG_ROOT_PATH.clone();
+    path.push("layouts");
+    path.push(format!("{}.json5", name));
+    Ok(path)
+}
+
+pub fn save_layout<T>(name: &Arc<str>, app: &mut AppState, overlays: &OverlayContainer<T>)
+where
+    T: Default,
+{
+    let (title, body) = match try_save_layout(name, app, overlays) {
+        Ok(()) => (format!("Layout {} saved", name), String::new()),
+        Err(e) => {
+            log::error!("Failed to save layout {}: {:?}", name, e);
+            (format!("Could not save layout {}", name), e.to_string())
+        }
+    };
+    Toast::new(ToastTopic::System, title.into(), body.into()).submit(app);
+}
+
+fn try_save_layout<T>(
+    name: &str,
+    app: &AppState,
+    overlays: &OverlayContainer<T>,
+) -> anyhow::Result<()>
+where
+    T: Default,
+{
+    let mut layout = SavedLayout::default();
+
+    for o in overlays.iter() {
+        let state = &o.state;
+        if state.show_hide {
+            layout.show_screens.arc_set(state.name.clone());
+        }
+        if let Some(curvature) = state.curvature {
+            layout.curve_values.arc_set(state.name.clone(), curvature);
+        }
+        if state.grabbable && matches!(state.relative_to, RelativeTo::None) {
+            let transform = state
+                .saved_transform
+                .unwrap_or_else(|| state.anchor_relative_transform(app));
+            layout
+                .transform_values
+                .arc_set(state.name.clone(), transform);
+        }
+        if let Some(kind) = state.window_kind.filter(|_| state.want_visible) {
+            layout.windows.arc_set(state.name.clone(), kind);
+        }
+    }
+
+    let path = layout_path(name)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(&layout)?)?;
+    Ok(())
+}
+
+pub fn restore_layout<T>(name: &Arc<str>, app: &mut AppState, overlays: &mut OverlayContainer<T>)
+where
+    T: Default,
+{
+    if let Err(e) = try_restore_layout(name, app, overlays) {
+        log::error!("Failed to restore layout {}: {:?}", name, e);
+        Toast::new(
+            ToastTopic::System,
+            format!("Could not restore layout {}", name).into(),
+            e.to_string().into(),
+        )
+        .submit(app);
+    }
+}
+
+fn try_restore_layout<T>(
+    name: &str,
+    app: &mut AppState,
+    overlays: &mut OverlayContainer<T>,
+) -> anyhow::Result<()>
+where
+    T: Default,
+{
+    let path = layout_path(name)?;
+    let json = std::fs::read_to_string(&path)
+        .map_err(|e| anyhow::anyhow!("{}: {}", path.to_string_lossy(), e))?;
+    let layout: SavedLayout = serde_json5::from_str(&json)?;
+
+    // windows that need to be re-opened pick these up on init
+    let config = &mut app.session.config;
+    for (name, transform) in layout.transform_values.iter() {
+        config.transform_values.arc_set(name.clone(), *transform);
+    }
+    for (name, curvature) in layout.curve_values.iter() {
+        config.curve_values.arc_set(name.clone(), *curvature);
+    }
+    if !layout.show_screens.is_empty() {
+        config.show_screens = layout.show_screens.clone();
+    }
+
+    for o in overlays.iter_mut() {
+        let state = &mut o.state;
+
+        if state.window_kind.is_some() {
+            state.want_visible = layout.windows.arc_get(&state.name).is_some();
+        }
+        if !layout.show_screens.is_empty() {
+            state.show_hide = layout.show_screens.arc_get(&state.name);
+        }
+        if let Some(curvature) = layout.curve_values.arc_get(&state.name) {
+            state.curvature = Some(*curvature);
+        }
+        if matches!(state.relative_to, RelativeTo::None) {
+            if let Some(transform) = layout.transform_values.arc_get(&state.name) {
+                state.saved_transform = Some(*transform);
+                state.animate_reset();
+            }
+        }
+        state.dirty = true;
+    }
+
+    for (name, kind) in layout.windows.iter() {
+        if overlays.mut_by_name(name).is_some() {
+            continue;
+        }
+        let action = match kind {
+            WindowKind::Mirror => WindowAction::ShowMirror,
+            WindowKind::Ui => WindowAction::ShowUi,
+        };
+        run_button_action(
+            &ButtonAction::Window {
+                target: name.clone(),
+                action,
+            },
+            app,
+        );
+    }
+
+    Ok(())
+}
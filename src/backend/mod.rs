@@ -1,6 +1,12 @@
 pub mod common;
+pub mod config_watch;
+pub mod dbus_service;
 pub mod input;
+pub mod input_record;
+pub mod ipc;
+pub mod layouts;
 pub mod notifications;
+pub mod tts;
 
 #[allow(clippy::all)]
 mod notifications_dbus;
@@ -14,6 +20,9 @@ pub mod openxr;
 #[cfg(feature = "uidev")]
 pub mod uidev;
 
+#[cfg(feature = "headless")]
+pub mod headless;
+
 #[cfg(feature = "osc")]
 pub mod osc;
 
@@ -1,11 +1,17 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use dbus::{
-    arg::{PropMap, Variant},
-    blocking::Connection,
-    channel::MatchingReceiver,
+    arg::{self, PropMap, RefArg, Variant},
+    blocking::{stdintf::org_freedesktop_dbus::RequestNameReply, Connection},
+    channel::{MatchingReceiver, Sender},
     message::MatchRule,
+    strings::ErrorName,
+    Message,
 };
 use serde::Deserialize;
 use std::{
+    collections::HashMap,
+    ffi::CString,
+    path::{Path, PathBuf},
     sync::{
         atomic::{AtomicBool, Ordering},
         mpsc, Arc,
@@ -14,45 +20,241 @@ use std::{
 };
 
 use crate::{
-    backend::notifications_dbus::OrgFreedesktopNotifications,
-    overlays::toast::{Toast, ToastTopic},
+    backend::{notifications_dbus::OrgFreedesktopNotifications, overlay::OverlayID},
+    overlays::toast::{dismiss_toast, Toast, ToastIcon, ToastTopic},
     state::AppState,
 };
 
+const NOTIFICATIONS_NAME: &str = "org.freedesktop.Notifications";
+const NOTIFICATIONS_PATH: &str = "/org/freedesktop/Notifications";
+
+// in order of preference
+const ICON_THEME_SIZES: [u32; 5] = [64, 48, 128, 256, 32];
+// anything larger than this is a bogus or hostile image-data hint
+const MAX_IMAGE_DATA_SIZE: i64 = 1024;
+
+const DEFAULT_TIMEOUT: f32 = 5.0;
+// expire_timeout of 0 means never, such toasts stay until clicked or pushed off the stack
+const NEVER_EXPIRE_TIMEOUT: f32 = 24. * 60. * 60.;
+
+const INTROSPECTION: &str = r#"<!DOCTYPE node PUBLIC "-//freedesktop//DTD D-BUS Object Introspection 1.0//EN"
+ "http://www.freedesktop.org/standards/dbus/1.0/introspect.dtd">
+<node>
+  <interface name="org.freedesktop.Notifications">
+    <method name="Notify">
+      <arg name="app_name" type="s" direction="in"/>
+      <arg name="replaces_id" type="u" direction="in"/>
+      <arg name="app_icon" type="s" direction="in"/>
+      <arg name="summary" type="s" direction="in"/>
+      <arg name="body" type="s" direction="in"/>
+      <arg name="actions" type="as" direction="in"/>
+      <arg name="hints" type="a{sv}" direction="in"/>
+      <arg name="expire_timeout" type="i" direction="in"/>
+      <arg name="id" type="u" direction="out"/>
+    </method>
+    <method name="CloseNotification">
+      <arg name="id" type="u" direction="in"/>
+    </method>
+    <method name="GetCapabilities">
+      <arg name="capabilities" type="as" direction="out"/>
+    </method>
+    <method name="GetServerInformation">
+      <arg name="name" type="s" direction="out"/>
+      <arg name="vendor" type="s" direction="out"/>
+      <arg name="version" type="s" direction="out"/>
+      <arg name="spec_version" type="s" direction="out"/>
+    </method>
+    <signal name="NotificationClosed">
+      <arg name="id" type="u"/>
+      <arg name="reason" type="u"/>
+    </signal>
+    <signal name="ActionInvoked">
+      <arg name="id" type="u"/>
+      <arg name="action_key" type="s"/>
+    </signal>
+  </interface>
+  <interface name="org.freedesktop.DBus.Introspectable">
+    <method name="Introspect">
+      <arg name="xml_data" type="s" direction="out"/>
+    </method>
+  </interface>
+</node>"#;
+
+/// As defined by the notification spec
+#[derive(Debug, Clone, Copy)]
+pub enum CloseReason {
+    Expired = 1,
+    Dismissed = 2,
+    Closed = 3,
+    Undefined = 4,
+}
+
+pub enum NotificationSignal {
+    ActionInvoked(u32, Arc<str>),
+    Closed(u32, CloseReason),
+}
+
+/// Tracks the toasts of notifications that we received as the notification server,
+/// so that clients can be told when they're acted upon or closed.
+#[derive(Default)]
+pub struct ServedNotifications {
+    overlays: HashMap<u32, OverlayID>,
+    signals: Vec<NotificationSignal>,
+}
+
+impl ServedNotifications {
+    /// Returns the toast of a previous notification with the same id, which should be dismissed
+    pub fn shown(&mut self, id: u32, overlay: OverlayID) -> Option<OverlayID> {
+        self.overlays
+            .insert(id, overlay)
+            .filter(|old| *old != overlay)
+    }
+
+    pub fn overlay(&self, id: u32) -> Option<OverlayID> {
+        self.overlays.get(&id).copied()
+    }
+
+    pub fn invoke_action(&mut self, id: u32, key: Arc<str>) {
+        if self.overlays.contains_key(&id) {
+            self.signals
+                .push(NotificationSignal::ActionInvoked(id, key));
+        }
+    }
+
+    pub fn toast_closed(&mut self, overlay: OverlayID, reason: CloseReason) {
+        let Some(id) = self
+            .overlays
+            .iter()
+            .find_map(|(id, o)| (*o == overlay).then_some(*id))
+        else {
+            return;
+        };
+        self.overlays.remove(&id);
+        self.signals.push(NotificationSignal::Closed(id, reason));
+    }
+}
+
+/// For served notifications that will not be shown, e.g. because they're muted.
+/// Clients are still told that the notification is gone.
+pub fn discard_served(app: &mut AppState, id: u32) {
+    match app.served_notifications.overlay(id) {
+        // it replaces a toast that is still up
+        Some(overlay) => dismiss_toast(app, overlay, CloseReason::Undefined),
+        None => app
+            .served_notifications
+            .signals
+            .push(NotificationSignal::Closed(id, CloseReason::Undefined)),
+    }
+}
+
 pub struct NotificationManager {
     rx_toast: mpsc::Receiver<Toast>,
     tx_toast: mpsc::SyncSender<Toast>,
+    /// Desktop notifications go through the icon thread before reaching rx_toast
+    tx_icon: mpsc::SyncSender<(Toast, Message)>,
+    rx_call: mpsc::Receiver<Message>,
+    tx_call: mpsc::SyncSender<Message>,
     dbus_data: Option<Connection>,
+    /// We own org.freedesktop.Notifications, as no other server was running
+    serving: bool,
+    /// Another server replaced us as the owner of org.freedesktop.Notifications
+    name_lost: Arc<AtomicBool>,
+    last_id: u32,
     running: Arc<AtomicBool>,
 }
 
 impl NotificationManager {
     pub fn new() -> Self {
         let (tx_toast, rx_toast) = mpsc::sync_channel(10);
+        let (tx_call, rx_call) = mpsc::sync_channel(32);
+        let (tx_icon, rx_icon) = mpsc::sync_channel(10);
+
+        let sender = tx_toast.clone();
+        let _ = std::thread::spawn(move || icon_thread(rx_icon, sender));
+
         Self {
             rx_toast,
             tx_toast,
+            tx_icon,
+            rx_call,
+            tx_call,
             dbus_data: None,
+            serving: false,
+            name_lost: Arc::new(AtomicBool::new(false)),
+            last_id: 0,
             running: Arc::new(AtomicBool::new(true)),
         }
     }
 
-    pub fn submit_pending(&self, app: &mut AppState) {
+    pub fn submit_pending(&mut self, app: &mut AppState) {
         if let Some(c) = &self.dbus_data {
             let _ = c.process(Duration::ZERO);
         }
 
+        if self.serving && self.name_lost.load(Ordering::Relaxed) {
+            log::info!("Another notification server took over, listening to it instead.");
+            self.serving = false;
+            self.dbus_data = None;
+            self.run_dbus(false);
+        }
+
+        if self.serving {
+            self.submit_server_calls(app);
+        }
+
         if app.session.config.notifications_enabled {
             self.rx_toast.try_iter().for_each(|toast| {
                 toast.submit(app);
             });
         } else {
             // consume without submitting
-            self.rx_toast.try_iter().last();
+            self.rx_toast.try_iter().for_each(|toast| {
+                if let Some(id) = toast.dbus_id {
+                    discard_served(app, id);
+                }
+            });
+        }
+    }
+
+    fn submit_server_calls(&mut self, app: &mut AppState) {
+        let Some(c) = &self.dbus_data else {
+            return;
+        };
+
+        let calls: Vec<Message> = self.rx_call.try_iter().collect();
+        for msg in calls {
+            let result = handle_server_call(&msg, app, &mut self.last_id, &self.tx_icon);
+            if msg.no_reply() {
+                continue;
+            }
+            let reply = result.unwrap_or_else(|e| {
+                let name = ErrorName::from("org.freedesktop.DBus.Error.Failed");
+                let text = CString::new(e.to_string()).unwrap_or_default();
+                msg.error(&name, &text)
+            });
+            let _ = c.send(reply);
+        }
+
+        for signal in app.served_notifications.signals.drain(..) {
+            let msg = match signal {
+                NotificationSignal::ActionInvoked(id, key) => {
+                    Message::new_signal(NOTIFICATIONS_PATH, NOTIFICATIONS_NAME, "ActionInvoked")
+                        .map(|m| m.append2(id, key.as_ref()))
+                }
+                NotificationSignal::Closed(id, reason) => Message::new_signal(
+                    NOTIFICATIONS_PATH,
+                    NOTIFICATIONS_NAME,
+                    "NotificationClosed",
+                )
+                .map(|m| m.append2(id, reason as u32)),
+            };
+            if let Ok(msg) = msg {
+                let _ = c.send(msg);
+            }
         }
     }
 
-    pub fn run_dbus(&mut self) {
+    pub fn run_dbus(&mut self, serve: bool) {
         let c = match Connection::new_session() {
             Ok(c) => c,
             Err(e) => {
@@ -64,6 +266,11 @@ impl NotificationManager {
             }
         };
 
+        if serve && self.try_serve(&c) {
+            self.dbus_data = Some(c);
+            return;
+        }
+
         let mut rule = MatchRule::new_method_call();
         rule.member = Some("Notify".into());
         rule.interface = Some("org.freedesktop.Notifications".into());
@@ -83,12 +290,12 @@ impl NotificationManager {
 
         match result {
             Ok(_) => {
-                let sender = self.tx_toast.clone();
+                let sender = self.tx_icon.clone();
                 c.start_receive(
                     rule,
                     Box::new(move |msg, _| {
                         if let Ok(toast) = parse_dbus(&msg) {
-                            match sender.try_send(toast) {
+                            match sender.try_send((toast, msg)) {
                                 Ok(_) => {}
                                 Err(e) => {
                                     log::error!("Failed to send notification: {:?}", e);
@@ -107,10 +314,10 @@ impl NotificationManager {
                     rule
                 };
 
-                let sender2 = self.tx_toast.clone();
+                let sender2 = self.tx_icon.clone();
                 let result = c.add_match(rule_with_eavesdrop, move |_: (), _, msg| {
-                    if let Ok(toast) = parse_dbus(msg) {
-                        match sender2.try_send(toast) {
+                    if let Some((toast, msg)) = parse_dbus(msg).ok().zip(msg.duplicate().ok()) {
+                        match sender2.try_send((toast, msg)) {
                             Ok(_) => {}
                             Err(e) => {
                                 log::error!("Failed to send notification: {:?}", e);
@@ -136,6 +343,54 @@ impl NotificationManager {
         self.dbus_data = Some(c);
     }
 
+    /// Takes over org.freedesktop.Notifications, unless another server already owns it
+    /// or can be started by D-Bus. Another server may replace us later on.
+    fn try_serve(&mut self, c: &Connection) -> bool {
+        if is_activatable(c) {
+            log::info!("A notification server can be started on demand, will not act as one.");
+            return false;
+        }
+
+        match c.request_name(NOTIFICATIONS_NAME, true, false, true) {
+            Ok(RequestNameReply::PrimaryOwner | RequestNameReply::AlreadyOwner) => {}
+            Ok(_) => return false,
+            Err(e) => {
+                log::warn!("Failed to request {}: {:?}", NOTIFICATIONS_NAME, e);
+                return false;
+            }
+        }
+
+        let name_lost = self.name_lost.clone();
+        let lost_rule = MatchRule::new_signal("org.freedesktop.DBus", "NameLost");
+        let result = c.add_match(lost_rule, move |(name,): (String,), _, _| {
+            if name == NOTIFICATIONS_NAME {
+                name_lost.store(true, Ordering::Relaxed);
+            }
+            true
+        });
+        if let Err(e) = result {
+            log::warn!("Failed to watch for NameLost: {:?}", e);
+        }
+
+        let mut rule = MatchRule::new_method_call();
+        rule.path = Some(NOTIFICATIONS_PATH.into());
+
+        let sender = self.tx_call.clone();
+        c.start_receive(
+            rule,
+            Box::new(move |msg, _| {
+                if let Err(e) = sender.try_send(msg) {
+                    log::error!("Failed to queue notification call: {:?}", e);
+                }
+                true
+            }),
+        );
+
+        log::info!("No notification server running, acting as one.");
+        self.serving = true;
+        true
+    }
+
     pub fn run_udp(&mut self) {
         let sender = self.tx_toast.clone();
         let running = self.running.clone();
@@ -175,12 +430,15 @@ impl NotificationManager {
                         continue;
                     }
 
+                    let icon = parse_xso_icon(&msg);
                     let toast = Toast::new(
                         ToastTopic::XSNotification,
                         msg.title,
                         msg.content.unwrap_or_else(|| "".into()),
                     )
+                    .with_icon(icon)
                     .with_timeout(msg.timeout.unwrap_or(5.))
+                    .with_app_name(msg.sourceApp.clone().unwrap_or_else(|| "".into()))
                     .with_sound(msg.volume.unwrap_or(-1.) >= 0.); // XSOverlay still plays at 0,
 
                     match sender.try_send(toast) {
@@ -256,6 +514,72 @@ impl DbusNotificationSender {
     }
 }
 
+fn is_activatable(c: &Connection) -> bool {
+    let proxy = c.with_proxy(
+        "org.freedesktop.DBus",
+        "/org/freedesktop/DBus",
+        Duration::from_millis(5000),
+    );
+    let result: Result<(Vec<String>,), dbus::Error> =
+        proxy.method_call("org.freedesktop.DBus", "ListActivatableNames", ());
+    result.is_ok_and(|(names,)| names.iter().any(|n| n == NOTIFICATIONS_NAME))
+}
+
+fn handle_server_call(
+    msg: &Message,
+    app: &mut AppState,
+    last_id: &mut u32,
+    tx_icon: &mpsc::SyncSender<(Toast, Message)>,
+) -> anyhow::Result<Message> {
+    let interface = msg.interface();
+    let member = msg.member();
+    let (Some(interface), Some(member)) = (interface.as_deref(), member.as_deref()) else {
+        anyhow::bail!("Missing interface or member");
+    };
+
+    if interface == "org.freedesktop.DBus.Introspectable" && member == "Introspect" {
+        return Ok(msg.method_return().append1(INTROSPECTION));
+    }
+    if interface != NOTIFICATIONS_NAME {
+        anyhow::bail!("Unknown interface: {}", interface);
+    }
+
+    match member {
+        "Notify" => {
+            let (_, replaces_id) = msg.read2::<&str, u32>()?;
+            let mut toast = parse_dbus(msg)?;
+
+            let id = if replaces_id > 0 {
+                replaces_id
+            } else {
+                *last_id = last_id.wrapping_add(1).max(1);
+                *last_id
+            };
+            toast.dbus_id = Some(id);
+
+            let copy = msg.duplicate().map_err(|e| anyhow::anyhow!(e))?;
+            if let Err(e) = tx_icon.try_send((toast, copy)) {
+                log::error!("Failed to send notification: {:?}", e);
+                discard_served(app, id);
+            }
+            Ok(msg.method_return().append1(id))
+        }
+        "CloseNotification" => {
+            let id: u32 = msg.read1()?;
+            if let Some(overlay) = app.served_notifications.overlay(id) {
+                dismiss_toast(app, overlay, CloseReason::Closed);
+            }
+            Ok(msg.method_return())
+        }
+        "GetCapabilities" => Ok(msg.method_return().append1(vec!["body", "actions"])),
+        "GetServerInformation" => Ok(msg
+            .method_return()
+            .append3("WlxOverlay-S", "wlx-overlay-s", env!("CARGO_PKG_VERSION"))
+            .append1("1.2")),
+        _ => anyhow::bail!("Unknown method: {}", member),
+    }
+}
+
 fn parse_dbus(msg: &dbus::Message) -> anyhow::Result<Toast> {
     let mut args = msg.iter_init();
     let app_name: String = args.read()?;
@@ -263,21 +587,195 @@ fn parse_dbus(msg: &dbus::Message) -> anyhow::Result<Toast> {
     let _app_icon: String = args.read()?;
     let summary: String = args.read()?;
     let body: String = args.read()?;
+    let actions: Vec<String> = args.read()?;
+    let hints: PropMap = args.read()?;
+    let expire_timeout: i32 = args.read()?;
+
+    let timeout = match expire_timeout {
+        0 => NEVER_EXPIRE_TIMEOUT,
+        ms if ms > 0 => ms as f32 / 1000.,
+        _ => DEFAULT_TIMEOUT,
+    };
+
+    let urgency = arg::prop_cast::<u8>(&hints, "urgency")
+        .copied()
+        .unwrap_or(1);
 
     let title = if summary.is_empty() {
-        app_name
+        app_name.clone()
     } else {
         summary
     };
 
     Ok(
         Toast::new(ToastTopic::DesktopNotification, title.into(), body.into())
-            .with_timeout(5.0)
-            .with_opacity(1.0),
+            .with_timeout(timeout)
+            .with_opacity(1.0)
+            .with_app_name(app_name.into())
+            .with_urgency(urgency)
+            .with_actions(
+                actions
+                    .chunks_exact(2)
+                    .map(|a| (a[0].as_str().into(), a[1].as_str().into()))
+                    .collect(),
+            ),
     )
     // leave the audio part to the desktop env
 }
 
+/// Loading icons can be slow, so desktop notifications get theirs here before being submitted
+fn icon_thread(rx: mpsc::Receiver<(Toast, Message)>, tx_toast: mpsc::SyncSender<Toast>) {
+    // ends once the manager and its dbus callbacks are gone
+    while let Ok((toast, msg)) = rx.recv() {
+        let toast = toast.with_icon(read_dbus_icon(&msg));
+        if let Err(e) = tx_toast.try_send(toast) {
+            log::error!("Failed to send notification: {:?}", e);
+        }
+    }
+}
+
+fn read_dbus_icon(msg: &Message) -> Option<ToastIcon> {
+    let mut args = msg.iter_init();
+    let _app_name: &str = args.read().ok()?;
+    let _replaces_id: u32 = args.read().ok()?;
+    let app_icon: &str = args.read().ok()?;
+    let _summary: &str = args.read().ok()?;
+    let _body: &str = args.read().ok()?;
+    let _actions: Vec<&str> = args.read().ok()?;
+    let hints: PropMap = args.read().ok()?;
+    parse_dbus_icon(&hints, app_icon)
+}
+
+/// Picks the icon in order of precedence, as per the notification spec
+fn parse_dbus_icon(hints: &PropMap, app_icon: &str) -> Option<ToastIcon> {
+    let image_data = ["image-data", "image_data"]
+        .iter()
+        .find_map(|k| hints.get(*k));
+    let image_path = ["image-path", "image_path"]
+        .iter()
+        .find_map(|k| arg::prop_cast::<String>(hints, k));
+
+    let result = if let Some(data) = image_data {
+        parse_image_data(&*data.0)
+    } else if let Some(path) = image_path {
+        load_icon(path)
+    } else if !app_icon.is_empty() {
+        load_icon(app_icon)
+    } else if let Some(data) = hints.get("icon_data") {
+        parse_image_data(&*data.0)
+    } else {
+        return None;
+    };
+
+    result
+        .map_err(|e| log::warn!("Could not load notification icon: {:?}", e))
+        .ok()
+}
+
+/// Raw pixels of the (iiibiiay) structure used by the image-data hint
+fn parse_image_data(data: &dyn RefArg) -> anyhow::Result<ToastIcon> {
+    let Some(mut fields) = data.as_iter() else {
+        anyhow::bail!("image-data is not a structure");
+    };
+    let mut next_int = || {
+        fields
+            .next()
+            .and_then(|f| f.as_i64())
+            .ok_or_else(|| anyhow::anyhow!("image-data is malformed"))
+    };
+    let width = next_int()?;
+    let height = next_int()?;
+    let rowstride = next_int()?;
+    let _has_alpha = next_int(); // implied by the channel count
+    let bits_per_sample = next_int()?;
+    let channels = next_int()?;
+
+    let Some(field) = fields.next() else {
+        anyhow::bail!("image-data is missing its data");
+    };
+
+    if bits_per_sample != 8 || !(3..=4).contains(&channels) {
+        anyhow::bail!(
+            "Unsupported image-data format: {} channels, {} bits",
+            channels,
+            bits_per_sample
+        );
+    }
+    if !(1..=MAX_IMAGE_DATA_SIZE).contains(&width) || !(1..=MAX_IMAGE_DATA_SIZE).contains(&height) {
+        anyhow::bail!("Unsupported image-data size: {}x{}", width, height);
+    }
+    let (width, height, channels) = (width as usize, height as usize, channels as usize);
+    let line_len = width * channels;
+    let Some(rowstride) = usize::try_from(rowstride).ok().filter(|r| *r >= line_len) else {
+        anyhow::bail!("image-data has an invalid rowstride: {}", rowstride);
+    };
+
+    let bytes: Vec<u8> = field
+        .as_iter()
+        .map(|i| i.filter_map(|b| b.as_u64()).map(|b| b as u8).collect())
+        .unwrap_or_default();
+
+    let needed = rowstride
+        .checked_mul(height - 1)
+        .and_then(|n| n.checked_add(line_len));
+    if !needed.is_some_and(|n| n <= bytes.len()) {
+        anyhow::bail!("image-data is truncated");
+    }
+
+    let mut rgba = Vec::with_capacity(width * height * 4);
+    for row in bytes.chunks(rowstride).take(height) {
+        for px in row[..line_len].chunks_exact(channels) {
+            rgba.extend_from_slice(&px[..3]);
+            rgba.push(px.get(3).copied().unwrap_or(255));
+        }
+    }
+
+    ToastIcon::from_rgba(width as _, height as _, rgba)
+}
+
+/// Accepts a file path, a file:// URI or a themed icon name
+fn load_icon(icon: &str) -> anyhow::Result<ToastIcon> {
+    let path = icon.strip_prefix("file://").unwrap_or(icon);
+    if path.starts_with('/') {
+        return ToastIcon::from_path(Path::new(path));
+    }
+    match find_themed_icon(path) {
+        Some(path) => ToastIcon::from_path(&path),
+        None => anyhow::bail!("Icon not found: {}", icon),
+    }
+}
+
+/// Only PNG icons from the hicolor theme and pixmaps are considered
+fn find_themed_icon(name: &str) -> Option<PathBuf> {
+    let xdg_dirs = xdg::BaseDirectories::new().ok()?;
+    ICON_THEME_SIZES
+        .iter()
+        .map(|s| format!("icons/hicolor/{s}x{s}/apps/{name}.png"))
+        .chain(std::iter::once(format!("pixmaps/{name}.png")))
+        .find_map(|p| xdg_dirs.find_data_file(p))
+}
+
+/// XSOverlay icons are either base64 encoded images or file paths
+fn parse_xso_icon(msg: &XsoMessage) -> Option<ToastIcon> {
+    let icon = msg.icon.as_ref().filter(|i| !i.is_empty())?;
+
+    let result = if msg.useBase64Icon.unwrap_or(false) {
+        BASE64
+            .decode(icon.as_bytes())
+            .map_err(anyhow::Error::from)
+            .and_then(|data| ToastIcon::from_encoded(&data))
+    } else if icon.starts_with('/') {
+        ToastIcon::from_path(Path::new(&**icon))
+    } else {
+        // built-in XSOverlay icons, such as "default" or "warning"
+        return None;
+    };
+
+    result
+        .map_err(|e| log::warn!("Could not load XSOverlay icon: {:?}", e))
+        .ok()
+}
+
 #[allow(non_snake_case)]
 #[derive(Debug, Deserialize)]
 struct XsoMessage {
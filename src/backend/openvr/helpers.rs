@@ -187,3 +187,30 @@ pub(super) fn adjust_gain(
 
     Some(())
 }
+
+pub(super) fn set_gain(settings: &mut SettingsManager, ch: ColorChannel, value: f32) -> Option<()> {
+    let channels = match ch {
+        ColorChannel::R => 0..1,
+        ColorChannel::G => 1..2,
+        ColorChannel::B => 2..3,
+        ColorChannel::All => 0..3,
+    };
+
+    // prevent user from turning everything black
+    let others: f32 = (0..3)
+        .filter(|i| !channels.contains(i))
+        .filter_map(|i| settings.get_float(STEAMVR_SECTION, COLOR_GAIN_CSTR[i]).ok())
+        .sum();
+    let min = if others < 0.11 {
+        0.1 / channels.len() as f32
+    } else {
+        0.0
+    };
+
+    for i in channels {
+        settings
+            .set_float(STEAMVR_SECTION, COLOR_GAIN_CSTR[i], value.clamp(min, 1.0))
+            .ok()?;
+    }
+    Some(())
+}
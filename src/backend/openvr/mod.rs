@@ -22,10 +22,15 @@ use vulkano::{
 use crate::{
     backend::{
         common::{BackendError, OverlayContainer},
+        config_watch::ConfigWatcher,
+        dbus_service::DbusService,
         input::interact,
+        input_record::InputSession,
+        ipc::IpcServer,
+        layouts,
         notifications::NotificationManager,
         openvr::{
-            helpers::adjust_gain,
+            helpers::{adjust_gain, set_gain},
             input::{set_action_manifest, OpenVrInputSource},
             lines::LinePool,
             manifest::{install_manifest, uninstall_manifest},
@@ -65,7 +70,11 @@ pub fn openvr_uninstall() {
     let _ = uninstall_manifest(&mut app_mgr);
 }
 
-pub fn openvr_run(running: Arc<AtomicBool>, show_by_default: bool) -> Result<(), BackendError> {
+pub fn openvr_run(
+    running: Arc<AtomicBool>,
+    show_by_default: bool,
+    input_session: &mut InputSession,
+) -> Result<(), BackendError> {
     let app_type = EVRApplicationType::VRApplication_Overlay;
     let Ok(context) = ovr_overlay::Context::init(app_type) else {
         log::warn!("Will not use OpenVR: Context init failed");
@@ -118,15 +127,29 @@ pub fn openvr_run(running: Arc<AtomicBool>, show_by_default: bool) -> Result<(),
 
     let mut overlays = OverlayContainer::<OpenVrOverlayData>::new(&mut state)?;
     let mut notifications = NotificationManager::new();
-    notifications.run_dbus();
+    notifications.run_dbus(state.session.config.notifications_server);
     notifications.run_udp();
 
+    let mut ipc = IpcServer::new();
+    ipc.run();
+
+    let mut dbus_service = DbusService::new();
+    dbus_service.run();
+
+    let mut config_watcher = ConfigWatcher::new();
+    config_watcher.run();
+
     let mut playspace = playspace::PlayspaceMover::new();
     playspace.playspace_changed(&mut compositor_mgr, &mut chaperone_mgr);
 
     #[cfg(feature = "osc")]
-    let mut osc_sender =
-        crate::backend::osc::OscSender::new(state.session.config.osc_out_port).ok();
+    let mut osc_receiver = if state.session.config.osc_actions.is_empty() {
+        None
+    } else {
+        crate::backend::osc::OscReceiver::new(state.session.config.osc_in_port)
+            .map_err(|e| log::error!("{}", e))
+            .ok()
+    };
 
     set_action_manifest(&mut input_mgr)?;
 
@@ -210,6 +233,15 @@ pub fn openvr_run(running: Arc<AtomicBool>, show_by_default: bool) -> Result<(),
 
         notifications.submit_pending(&mut state);
 
+        #[cfg(feature = "osc")]
+        if let Some(ref mut receiver) = osc_receiver {
+            receiver.submit_pending(&mut state);
+        }
+
+        ipc.submit_pending(&mut state, &mut overlays);
+        dbus_service.submit_pending(&mut state, &mut overlays);
+        config_watcher.submit_pending(&mut state, &mut overlays);
+
         state.tasks.retrieve_due(&mut due_tasks);
 
         let mut removed_overlays = overlays.update(&mut state)?;
@@ -255,6 +287,9 @@ pub fn openvr_run(running: Arc<AtomicBool>, show_by_default: bool) -> Result<(),
                     SystemTask::ColorGain(channel, value) => {
                         let _ = adjust_gain(&mut settings_mgr, channel, value);
                     }
+                    SystemTask::SetColorGain(channel, value) => {
+                        let _ = set_gain(&mut settings_mgr, channel, value);
+                    }
                     SystemTask::FixFloor => {
                         playspace.fix_floor(&mut chaperone_mgr, &state.input_state);
                     }
@@ -264,6 +299,23 @@ pub fn openvr_run(running: Arc<AtomicBool>, show_by_default: bool) -> Result<(),
                     SystemTask::ShowHide => {
                         overlays.show_hide(&mut state);
                     }
+                    SystemTask::ApplyConfig => {
+                        overlays.apply_config(&mut state);
+                    }
+                    SystemTask::SaveLayout(name) => {
+                        layouts::save_layout(&name, &mut state, &overlays);
+                    }
+                    SystemTask::RestoreLayout(name) => {
+                        layouts::restore_layout(&name, &mut state, &mut overlays);
+                    }
+                    SystemTask::ApplyTheme => {
+                        overlays.rebuild_panels(&mut state);
+                    }
+                    SystemTask::RebuildKeyboard => {
+                        if let Err(e) = overlays.rebuild_keyboard(&mut state) {
+                            log::error!("Failed to rebuild keyboard: {:?}", e);
+                        }
+                    }
                 },
                 #[cfg(feature = "wayvr")]
                 TaskType::WayVR(action) => {
@@ -281,6 +333,14 @@ pub fn openvr_run(running: Arc<AtomicBool>, show_by_default: bool) -> Result<(),
             &mut system_mgr,
             &mut state,
         );
+        if !input_session.update(&mut state.input_state) {
+            Toast::new(
+                ToastTopic::System,
+                "Replay finished".into(),
+                "Using live input.".into(),
+            )
+            .submit(&mut state);
+        }
         state.input_state.post_update(&state.session);
 
         if state
@@ -323,7 +383,7 @@ pub fn openvr_run(running: Arc<AtomicBool>, show_by_default: bool) -> Result<(),
         }
 
         #[cfg(feature = "osc")]
-        if let Some(ref mut sender) = osc_sender {
+        if let Some(sender) = state.osc_sender.clone() {
             let _ = sender.send_params(&overlays, &state);
         };
 
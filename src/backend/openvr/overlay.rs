@@ -9,7 +9,7 @@ use ovr_overlay::{
 use vulkano::{Handle, VulkanObject};
 
 use crate::{
-    backend::overlay::{OverlayData, RelativeTo},
+    backend::overlay::{OverlayBackendData, OverlayData, RelativeTo},
     graphics::WlxGraphics,
     state::AppState,
 };
@@ -27,6 +27,18 @@ pub(super) struct OpenVrOverlayData {
     pub(super) relative_to: RelativeTo,
 }
 
+impl OverlayBackendData for OpenVrOverlayData {
+    fn initialized(&self) -> bool {
+        self.handle.is_some()
+    }
+    fn resumed(&self) -> bool {
+        self.visible
+    }
+    fn backend_replaced(&mut self) {
+        self.last_image = None;
+    }
+}
+
 impl OverlayData<OpenVrOverlayData> {
     pub(super) fn initialize(
         &mut self,
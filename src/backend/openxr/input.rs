@@ -7,6 +7,7 @@ use std::{
 use glam::{bool, Affine3A, Quat, Vec3};
 use libmonado_rs::{Device, Monado};
 use openxr::{self as xr, Quaternionf, Vector3f};
+use schemars::{schema::RootSchema, JsonSchema};
 use serde::{Deserialize, Serialize};
 
 use crate::{
@@ -638,7 +639,7 @@ fn suggest_bindings(instance: &xr::Instance, hands: &[&OpenXrHandSource; 2]) ->
     Ok(())
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 struct OpenXrActionConfAction {
     left: Option<String>,
     right: Option<String>,
@@ -647,7 +648,7 @@ struct OpenXrActionConfAction {
     triple_click: Option<bool>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 struct OpenXrActionConfProfile {
     profile: String,
     pose: Option<OpenXrActionConfAction>,
@@ -667,6 +668,11 @@ struct OpenXrActionConfProfile {
 
 const DEFAULT_PROFILES: &str = include_str!("openxr_actions.json5");
 
+/// Schema of openxr_actions.json5
+pub fn action_profiles_schema() -> RootSchema {
+    schemars::schema_for!(Vec<OpenXrActionConfProfile>)
+}
+
 fn load_action_profiles() -> anyhow::Result<Vec<OpenXrActionConfProfile>> {
     let mut profiles: Vec<OpenXrActionConfProfile> =
         serde_json5::from_str(DEFAULT_PROFILES).unwrap(); // want panic
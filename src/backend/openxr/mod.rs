@@ -17,7 +17,12 @@ use vulkano::{command_buffer::CommandBufferUsage, Handle, VulkanObject};
 use crate::{
     backend::{
         common::{BackendError, OverlayContainer},
+        config_watch::ConfigWatcher,
+        dbus_service::DbusService,
         input::interact,
+        input_record::InputSession,
+        ipc::IpcServer,
+        layouts,
         notifications::NotificationManager,
         openxr::{lines::LinePool, overlay::OpenXrOverlayData},
         overlay::OverlayData,
@@ -42,6 +47,8 @@ mod playspace;
 mod skybox;
 mod swapchain;
 
+pub use input::action_profiles_schema;
+
 const VIEW_TYPE: xr::ViewConfigurationType = xr::ViewConfigurationType::PRIMARY_STEREO;
 const VIEW_COUNT: u32 = 2;
 static FRAME_COUNTER: AtomicUsize = AtomicUsize::new(0);
@@ -57,7 +64,11 @@ struct XrState {
     stage_offset: Affine3A,
 }
 
-pub fn openxr_run(running: Arc<AtomicBool>, show_by_default: bool) -> Result<(), BackendError> {
+pub fn openxr_run(
+    running: Arc<AtomicBool>,
+    show_by_default: bool,
+    input_session: &mut InputSession,
+) -> Result<(), BackendError> {
     let (xr_instance, system) = match helpers::init_xr() {
         Ok((xr_instance, system)) => (xr_instance, system),
         Err(e) => {
@@ -94,9 +105,18 @@ pub fn openxr_run(running: Arc<AtomicBool>, show_by_default: bool) -> Result<(),
     let mut lines = LinePool::new(app_state.graphics.clone())?;
 
     let mut notifications = NotificationManager::new();
-    notifications.run_dbus();
+    notifications.run_dbus(app_state.session.config.notifications_server);
     notifications.run_udp();
 
+    let mut ipc = IpcServer::new();
+    ipc.run();
+
+    let mut dbus_service = DbusService::new();
+    dbus_service.run();
+
+    let mut config_watcher = ConfigWatcher::new();
+    config_watcher.run();
+
     let mut delete_queue = vec![];
 
     let mut monado = Monado::auto_connect()
@@ -110,8 +130,13 @@ pub fn openxr_run(running: Arc<AtomicBool>, show_by_default: bool) -> Result<(),
     });
 
     #[cfg(feature = "osc")]
-    let mut osc_sender =
-        crate::backend::osc::OscSender::new(app_state.session.config.osc_out_port).ok();
+    let mut osc_receiver = if app_state.session.config.osc_actions.is_empty() {
+        None
+    } else {
+        crate::backend::osc::OscReceiver::new(app_state.session.config.osc_in_port)
+            .map_err(|e| log::error!("{}", e))
+            .ok()
+    };
 
     let (session, mut frame_wait, mut frame_stream) = unsafe {
         let raw_session = helpers::create_overlay_session(
@@ -280,6 +305,14 @@ pub fn openxr_run(running: Arc<AtomicBool>, show_by_default: bool) -> Result<(),
 
         app_state.input_state.pre_update();
         input_source.update(&xr_state, &mut app_state)?;
+        if !input_session.update(&mut app_state.input_state) {
+            Toast::new(
+                ToastTopic::System,
+                "Replay finished".into(),
+                "Using live input.".into(),
+            )
+            .submit(&mut app_state);
+        }
         app_state.input_state.post_update(&app_state.session);
 
         if app_state
@@ -305,7 +338,7 @@ pub fn openxr_run(running: Arc<AtomicBool>, show_by_default: bool) -> Result<(),
         }
 
         #[cfg(feature = "osc")]
-        if let Some(ref mut sender) = osc_sender {
+        if let Some(sender) = app_state.osc_sender.clone() {
             let _ = sender.send_params(&overlays, &app_state);
         };
 
@@ -444,6 +477,15 @@ pub fn openxr_run(running: Arc<AtomicBool>, show_by_default: bool) -> Result<(),
 
         notifications.submit_pending(&mut app_state);
 
+        #[cfg(feature = "osc")]
+        if let Some(ref mut receiver) = osc_receiver {
+            receiver.submit_pending(&mut app_state);
+        }
+
+        ipc.submit_pending(&mut app_state, &mut overlays);
+        dbus_service.submit_pending(&mut app_state, &mut overlays);
+        config_watcher.submit_pending(&mut app_state, &mut overlays);
+
         app_state.tasks.retrieve_due(&mut due_tasks);
         while let Some(task) = due_tasks.pop_front() {
             match task {
@@ -498,6 +540,23 @@ pub fn openxr_run(running: Arc<AtomicBool>, show_by_default: bool) -> Result<(),
                     SystemTask::ShowHide => {
                         overlays.show_hide(&mut app_state);
                     }
+                    SystemTask::ApplyConfig => {
+                        overlays.apply_config(&mut app_state);
+                    }
+                    SystemTask::SaveLayout(name) => {
+                        layouts::save_layout(&name, &mut app_state, &overlays);
+                    }
+                    SystemTask::RestoreLayout(name) => {
+                        layouts::restore_layout(&name, &mut app_state, &mut overlays);
+                    }
+                    SystemTask::ApplyTheme => {
+                        overlays.rebuild_panels(&mut app_state);
+                    }
+                    SystemTask::RebuildKeyboard => {
+                        if let Err(e) = overlays.rebuild_keyboard(&mut app_state) {
+                            log::error!("Failed to rebuild keyboard: {:?}", e);
+                        }
+                    }
                     _ => {}
                 },
                 #[cfg(feature = "wayvr")]
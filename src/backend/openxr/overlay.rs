@@ -7,7 +7,7 @@ use super::{helpers, swapchain::SwapchainRenderData, CompositionLayer, XrState};
 use crate::{
     backend::{
         openxr::swapchain::{create_swapchain_render_data, SwapchainOpts},
-        overlay::OverlayData,
+        overlay::{OverlayBackendData, OverlayData},
     },
     graphics::WlxCommandBuffer,
     state::AppState,
@@ -22,6 +22,20 @@ pub struct OpenXrOverlayData {
     pub(super) init: bool,
 }
 
+impl OverlayBackendData for OpenXrOverlayData {
+    fn initialized(&self) -> bool {
+        self.init
+    }
+    fn resumed(&self) -> bool {
+        self.last_visible
+    }
+    fn backend_replaced(&mut self) {
+        // the new backend may render at a different size
+        self.swapchain = None;
+        self.last_view = None;
+    }
+}
+
 impl OverlayData<OpenXrOverlayData> {
     pub(super) fn present_xr<'a>(
         &'a mut self,
@@ -1,23 +1,187 @@
 use std::{
+    cell::RefCell,
+    collections::HashMap,
     net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket},
-    time::Instant,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    time::{Duration, Instant},
 };
 
 use anyhow::bail;
 use rosc::{OscMessage, OscPacket, OscType};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
-use crate::overlays::{keyboard::KEYBOARD_NAME, watch::WATCH_NAME};
+use crate::overlays::{keyboard::KEYBOARD_NAME, toast::TOAST_NAME, watch::WATCH_NAME};
 
 use crate::{
     backend::input::TrackedDeviceRole,
+    gui::modular::button::{run_button_action, ButtonAction},
     state::AppState,
 };
 
 use super::common::OverlayContainer;
 
+#[derive(Deserialize, Serialize, Clone, Copy, JsonSchema)]
+pub enum OscDevice {
+    Hmd,
+    LeftHand,
+    RightHand,
+    /// Sent once per tracker, `{n}` in the address is replaced with the tracker's index
+    Tracker,
+}
+
+impl OscDevice {
+    fn role(&self) -> TrackedDeviceRole {
+        match self {
+            OscDevice::Hmd => TrackedDeviceRole::Hmd,
+            OscDevice::LeftHand => TrackedDeviceRole::LeftHand,
+            OscDevice::RightHand => TrackedDeviceRole::RightHand,
+            OscDevice::Tracker => TrackedDeviceRole::Tracker,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, JsonSchema)]
+#[serde(tag = "source")]
+pub enum OscParamSource {
+    /// Number of visible interactable overlays, not counting the watch and keyboard
+    OpenOverlayCount,
+    /// 1 if the named overlay is visible, otherwise 0
+    OverlayVisible {
+        overlay: Arc<str>,
+    },
+    /// Battery level from 0 to 1, or -1 if unknown
+    Battery {
+        device: OscDevice,
+    },
+    /// 1 if the device is charging, otherwise 0
+    Charging {
+        device: OscDevice,
+    },
+    Constant {
+        value: f32,
+    },
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, JsonSchema)]
+pub enum OscValueType {
+    /// True if the value is above 0
+    Bool,
+    /// The value multiplied by scale, rounded
+    Int,
+    /// The value multiplied by scale
+    Float,
+}
+
+#[derive(Deserialize, Serialize, Clone, JsonSchema)]
+pub struct OscParam {
+    pub address: Arc<str>,
+    #[serde(flatten)]
+    pub source: OscParamSource,
+    #[serde(rename = "type", default = "def_float")]
+    pub value_type: OscValueType,
+    #[serde(default = "def_one")]
+    pub scale: f32,
+    #[serde(default = "def_interval_ms")]
+    pub interval_ms: u64,
+}
+
+fn def_float() -> OscValueType {
+    OscValueType::Float
+}
+
+fn def_one() -> f32 {
+    1.0
+}
+
+fn def_interval_ms() -> u64 {
+    100
+}
+
+fn param(address: &str, source: OscParamSource, value_type: OscValueType) -> OscParam {
+    OscParam {
+        address: address.into(),
+        source,
+        value_type,
+        scale: 1.0,
+        interval_ms: 100,
+    }
+}
+
+fn battery_params(name: &str, device: OscDevice) -> [OscParam; 2] {
+    [
+        OscParam {
+            interval_ms: 10000,
+            ..param(
+                &format!("/avatar/parameters/{name}Battery"),
+                OscParamSource::Battery { device },
+                OscValueType::Float,
+            )
+        },
+        OscParam {
+            interval_ms: 10000,
+            ..param(
+                &format!("/avatar/parameters/{name}Charging"),
+                OscParamSource::Charging { device },
+                OscValueType::Bool,
+            )
+        },
+    ]
+}
+
+pub fn def_osc_out_params() -> Vec<OscParam> {
+    let mut params = vec![
+        param(
+            "/avatar/parameters/isOverlayOpen",
+            OscParamSource::OpenOverlayCount,
+            OscValueType::Bool,
+        ),
+        param(
+            "/avatar/parameters/isKeyboardOpen",
+            OscParamSource::OverlayVisible {
+                overlay: KEYBOARD_NAME.into(),
+            },
+            OscValueType::Bool,
+        ),
+        param(
+            "/avatar/parameters/isWristVisible",
+            OscParamSource::OverlayVisible {
+                overlay: WATCH_NAME.into(),
+            },
+            OscValueType::Bool,
+        ),
+        param(
+            "/avatar/parameters/openOverlayCount",
+            OscParamSource::OpenOverlayCount,
+            OscValueType::Int,
+        ),
+        // legacy OVR Toolkit style (int 0-100)
+        OscParam {
+            scale: 100.0,
+            interval_ms: 10000,
+            ..param(
+                "/avatar/parameters/hmdBattery",
+                OscParamSource::Battery {
+                    device: OscDevice::Hmd,
+                },
+                OscValueType::Int,
+            )
+        },
+    ];
+    // XSOverlay style (float 0-1)
+    params.extend(battery_params("headset", OscDevice::Hmd));
+    params.extend(battery_params("leftController", OscDevice::LeftHand));
+    params.extend(battery_params("rightController", OscDevice::RightHand));
+    params.extend(battery_params("tracker{n}", OscDevice::Tracker));
+    params
+}
+
 pub struct OscSender {
-    last_sent_overlay: Instant,
-    last_sent_battery: Instant,
+    /// By address, so that the timers survive changes to osc_out_params
+    last_sent: RefCell<HashMap<Arc<str>, Instant>>,
     upstream: UdpSocket,
 }
 
@@ -35,8 +199,7 @@ impl OscSender {
 
         Ok(Self {
             upstream,
-            last_sent_overlay: Instant::now(),
-            last_sent_battery: Instant::now(),
+            last_sent: RefCell::new(HashMap::new()),
         })
     }
 
@@ -53,101 +216,238 @@ impl OscSender {
         Ok(())
     }
 
-    pub fn send_params<D>(&mut self, overlays: &OverlayContainer<D>, app: &AppState) -> anyhow::Result<()>
+    pub fn send_params<D>(
+        &self,
+        overlays: &OverlayContainer<D>,
+        app: &AppState,
+    ) -> anyhow::Result<()>
     where
         D: Default,
     {
-        // send overlay data every 0.1 seconds
-        if self.last_sent_overlay.elapsed().as_millis() >= 100 {
-            self.last_sent_overlay = Instant::now();
-
-            let mut num_overlays = 0;
-            let mut has_keyboard = false;
-            let mut has_wrist = false;
+        let params = &app.session.config.osc_out_params;
+        let mut last_sent = self.last_sent.borrow_mut();
+        last_sent.retain(|addr, _| params.iter().any(|p| p.address == *addr));
 
-            for o in overlays.iter() {
-                if !o.state.want_visible {
-                    continue;
-                }
-                match o.state.name.as_ref() {
-                    WATCH_NAME => has_wrist = true,
-                    KEYBOARD_NAME => has_keyboard = true,
-                    _ => {
-                        if o.state.interactable {
-                            num_overlays += 1
-                        }
-                    }
-                }
+        for param in params.iter() {
+            if last_sent
+                .get(&param.address)
+                .is_some_and(|t| t.elapsed().as_millis() < param.interval_ms as u128)
+            {
+                continue;
             }
+            last_sent.insert(param.address.clone(), Instant::now());
 
-            self.send_message(
-                "/avatar/parameters/isOverlayOpen".into(),
-                vec![OscType::Bool(num_overlays > 0)],
-            )?;
-            self.send_message(
-                "/avatar/parameters/isKeyboardOpen".into(),
-                vec![OscType::Bool(has_keyboard)],
-            )?;
-            self.send_message(
-                "/avatar/parameters/isWristVisible".into(),
-                vec![OscType::Bool(has_wrist)],
-            )?;
-            self.send_message(
-                "/avatar/parameters/openOverlayCount".into(),
-                vec![OscType::Int(num_overlays)],
-            )?;
-
+            for (addr, value) in param_values(param, overlays, app) {
+                let value = value * param.scale;
+                let arg = match param.value_type {
+                    OscValueType::Bool => OscType::Bool(value > 0.0),
+                    OscValueType::Int => OscType::Int(value.round() as i32),
+                    OscValueType::Float => OscType::Float(value),
+                };
+                self.send_message(addr, vec![arg])?;
+            }
         }
 
-        // send battery levels every 10 seconds
-        if self.last_sent_battery.elapsed().as_millis() >= 10000 {
-            self.last_sent_battery = Instant::now();
+        Ok(())
+    }
+}
 
-            let mut tracker_idx = 0;
+fn param_values<D>(
+    param: &OscParam,
+    overlays: &OverlayContainer<D>,
+    app: &AppState,
+) -> Vec<(String, f32)>
+where
+    D: Default,
+{
+    let single = |value: f32| vec![(param.address.to_string(), value)];
 
-            for device in &app.input_state.devices {
+    match &param.source {
+        OscParamSource::OpenOverlayCount => single(
+            overlays
+                .iter()
+                .filter(|o| {
+                    o.state.want_visible
+                        && o.state.interactable
+                        && !matches!(o.state.name.as_ref(), WATCH_NAME | KEYBOARD_NAME)
+                        && !o.state.name.starts_with(TOAST_NAME)
+                })
+                .count() as f32,
+        ),
+        OscParamSource::OverlayVisible { overlay } => single(
+            if overlays
+                .iter()
+                .any(|o| o.state.want_visible && o.state.name == *overlay)
+            {
+                1.0
+            } else {
+                0.0
+            },
+        ),
+        OscParamSource::Battery { device } | OscParamSource::Charging { device } => app
+            .input_state
+            .devices
+            .iter()
+            .filter(|d| d.role == device.role())
+            .enumerate()
+            .map(|(idx, d)| {
+                let value = match param.source {
+                    OscParamSource::Charging { .. } => {
+                        if d.charging {
+                            1.0
+                        } else {
+                            0.0
+                        }
+                    }
+                    _ => d.soc.unwrap_or(-1.0),
+                };
+                (param.address.replace("{n}", &idx.to_string()), value)
+            })
+            .collect(),
+        OscParamSource::Constant { value } => single(*value),
+    }
+}
 
-                // soc is the battery level (set to device status.charge)
-                let level = device.soc.unwrap_or(-1.0);
-                let parameter;
+pub struct OscReceiver {
+    rx_message: mpsc::Receiver<OscMessage>,
+    running: Arc<AtomicBool>,
+    last_high: HashMap<String, bool>,
+}
 
-                match device.role {
-                    TrackedDeviceRole::None =>      {parameter = String::from("")}
-                    TrackedDeviceRole::Hmd =>       {
-                        // XSOverlay style (float)
-                        // this parameter doesn't exist, but it's a stepping stone for 0-1 values (i presume XSOverlay would use the full name headset and not the abbreviation hmd)
-                        parameter = String::from("headset");
+impl OscReceiver {
+    pub fn new(receive_port: u16) -> anyhow::Result<Self> {
+        let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), receive_port);
 
-                        // legacy OVR Toolkit style (int)
-                        // according to their docs, OVR Toolkit is now supposed to use float 0-1.
-                        // as of 20 Nov 2024 they still use int 0-100, but this may change in a future update.
-                        //TODO: remove once their implementation matches the docs
-                        self.send_message(
-                            "/avatar/parameters/hmdBattery".into(),
-                                        vec![OscType::Int((level * 100.0f32).round() as i32)],
-                        )?;
+        let Ok(socket) = UdpSocket::bind(addr) else {
+            bail!(
+                "Failed to bind OSC receive socket @ {} - OSC actions will not function.",
+                addr
+            );
+        };
+        if let Err(err) = socket.set_read_timeout(Some(Duration::from_millis(200))) {
+            log::error!("Failed to set read timeout: {:?}", err);
+        }
 
+        let (tx_message, rx_message) = mpsc::sync_channel(32);
+        let running = Arc::new(AtomicBool::new(true));
+
+        let _ = std::thread::spawn({
+            let running = running.clone();
+            move || {
+                let mut buf = [0u8; rosc::decoder::MTU];
+                while running.load(Ordering::Relaxed) {
+                    let Ok((num_bytes, _)) = socket.recv_from(&mut buf) else {
+                        continue;
+                    };
+                    match rosc::decoder::decode_udp(&buf[..num_bytes]) {
+                        Ok((_, packet)) => {
+                            for message in flatten_packet(packet) {
+                                if let Err(e) = tx_message.try_send(message) {
+                                    log::error!("Failed to queue OSC message: {:?}", e);
+                                }
+                            }
+                        }
+                        Err(e) => {
+                            log::debug!("Failed to decode OSC packet: {:?}", e);
+                        }
                     }
-                    TrackedDeviceRole::LeftHand =>  {parameter = String::from("leftController")}
-                    TrackedDeviceRole::RightHand => {parameter = String::from("rightController")}
-                    TrackedDeviceRole::Tracker =>   {parameter = format!("tracker{tracker_idx}"); tracker_idx += 1;}
                 }
+                log::info!("OSC receiver stopped.");
+            }
+        });
+
+        log::info!("Listening for OSC messages on {}", addr);
+        Ok(Self {
+            rx_message,
+            running,
+            last_high: HashMap::new(),
+        })
+    }
 
-                // send battery parameters
-                if !parameter.is_empty() {
-
-                    self.send_message(
-                        format!("/avatar/parameters/{parameter}Battery").into(),
-                                    vec![OscType::Float(level)],
-                    )?;
-                    self.send_message(
-                        format!("/avatar/parameters/{parameter}Charging").into(),
-                                    vec![OscType::Bool(device.charging)],
-                    )?;
+    pub fn submit_pending(&mut self, app: &mut AppState) {
+        for message in self.rx_message.try_iter() {
+            let Some(actions) = app.session.config.osc_actions.get(message.addr.as_str()) else {
+                log::trace!("No OSC action bound to {}", message.addr);
+                continue;
+            };
+
+            // avatar parameters are re-sent while held and when they reset,
+            // only act when an address goes from low to high
+            let high = match message.args.first() {
+                Some(OscType::Bool(b)) => Some(*b),
+                Some(OscType::Int(i)) => Some(*i != 0),
+                Some(OscType::Float(f)) => Some(*f > 0.),
+                _ => None,
+            };
+            if let Some(high) = high {
+                let was_high = self
+                    .last_high
+                    .insert(message.addr.clone(), high)
+                    .unwrap_or(false);
+                if !high || was_high {
+                    continue;
                 }
             }
+
+            let actions: Vec<ButtonAction> = actions
+                .iter()
+                .map(|a| with_osc_args(a, &message.args))
+                .collect();
+            for action in actions.iter() {
+                run_button_action(action, app);
+            }
         }
+    }
+}
 
-        Ok(())
+impl Drop for OscReceiver {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
+
+fn flatten_packet(packet: OscPacket) -> Vec<OscMessage> {
+    match packet {
+        OscPacket::Message(message) => vec![message],
+        OscPacket::Bundle(bundle) => bundle
+            .content
+            .into_iter()
+            .flat_map(flatten_packet)
+            .collect(),
+    }
+}
+
+/// Replaces `{0}`, `{1}`, ... in toast texts with the arguments of the OSC message.
+fn with_osc_args(action: &ButtonAction, args: &[OscType]) -> ButtonAction {
+    let ButtonAction::Toast {
+        message,
+        body,
+        seconds,
+    } = action
+    else {
+        return action.clone();
+    };
+
+    let replace = |text: &str| -> Arc<str> {
+        let mut text = text.to_string();
+        for (idx, arg) in args.iter().enumerate() {
+            let value = match arg {
+                OscType::String(s) => s.clone(),
+                OscType::Int(i) => i.to_string(),
+                OscType::Long(l) => l.to_string(),
+                OscType::Float(f) => f.to_string(),
+                OscType::Double(d) => d.to_string(),
+                OscType::Bool(b) => b.to_string(),
+                _ => continue,
+            };
+            text = text.replace(&format!("{{{}}}", idx), &value);
+        }
+        text.into()
+    };
+
+    ButtonAction::Toast {
+        message: replace(message),
+        body: body.as_ref().map(|b| replace(b)),
+        seconds: *seconds,
     }
 }
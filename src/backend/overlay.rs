@@ -4,11 +4,13 @@ use std::{
         atomic::{AtomicUsize, Ordering},
         Arc,
     },
+    time::Instant,
 };
 
 use anyhow::Ok;
 use glam::{Affine2, Affine3A, Mat3A, Quat, Vec2, Vec3, Vec3A};
-use serde::Deserialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use vulkano::image::view::ImageView;
 
 use crate::{
@@ -25,7 +27,17 @@ pub trait OverlayBackend: OverlayRenderer + InteractionHandler {
     fn set_interaction(&mut self, interaction: Box<dyn InteractionHandler>);
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Default)]
+/// Per-overlay data kept by the presentation backend
+pub trait OverlayBackendData: Default {
+    /// Whether the overlay's backend has been through `init`
+    fn initialized(&self) -> bool;
+    /// Whether the overlay's backend is currently resumed
+    fn resumed(&self) -> bool;
+    /// Drop anything that was derived from the previous backend's images
+    fn backend_replaced(&mut self);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Default, JsonSchema)]
 pub struct OverlayID(pub usize);
 
 pub struct OverlayState {
@@ -51,6 +63,9 @@ pub struct OverlayState {
     pub primary_pointer: Option<usize>,
     pub interaction_transform: Affine2,
     pub birthframe: usize,
+    /// Set on windows opened by a button, so layouts can re-open them
+    pub window_kind: Option<WindowKind>,
+    pub move_animation: Option<MoveAnimation>,
 }
 
 impl Default for OverlayState {
@@ -78,6 +93,8 @@ impl Default for OverlayState {
             primary_pointer: None,
             interaction_transform: Affine2::IDENTITY,
             birthframe: 0,
+            window_kind: None,
+            move_animation: None,
         }
     }
 }
@@ -138,9 +155,30 @@ impl OverlayState {
         if let Some(parent) = self.parent_transform(app) {
             self.transform = parent * self.get_transform();
             self.dirty = true;
+        } else if let Some(animation) = self.move_animation.as_ref() {
+            let target = self.get_anchor(app) * self.get_transform();
+            let t = animation.progress();
+            self.transform = animation.lerp(target, t);
+            if t >= 1. {
+                self.move_animation = None;
+            }
+            self.dirty = true;
         }
     }
 
+    /// Glide from the current transform to where `reset` would put the overlay.
+    pub fn animate_reset(&mut self) {
+        self.move_animation = Some(MoveAnimation {
+            from: self.transform,
+            start: Instant::now(),
+        });
+    }
+
+    /// The transform that `saved_transform` would need to keep the overlay where it is.
+    pub fn anchor_relative_transform(&self, app: &AppState) -> Affine3A {
+        self.get_anchor(app).inverse() * self.transform
+    }
+
     pub fn reset(&mut self, app: &mut AppState, hard_reset: bool) {
         if hard_reset {
             self.saved_transform = None;
@@ -250,6 +288,37 @@ where
     }
 }
 
+impl<T> OverlayData<T>
+where
+    T: OverlayBackendData,
+{
+    /// Swap out the backend of a live overlay, e.g. after its config changed.
+    /// The new backend is brought to the same lifecycle stage as the old one.
+    pub fn replace_backend(
+        &mut self,
+        app: &mut AppState,
+        mut backend: Box<dyn OverlayBackend>,
+    ) -> anyhow::Result<()> {
+        let initialized = self.data.initialized();
+        let resumed = self.data.resumed();
+
+        if initialized {
+            backend.init(app)?;
+        }
+
+        let mut old = std::mem::replace(&mut self.backend, backend);
+        if resumed {
+            if let Err(e) = old.pause(app) {
+                log::error!("{}: Failed to pause old backend: {:?}", self.state.name, e);
+            }
+            self.backend.resume(app)?;
+        }
+
+        self.data.backend_replaced();
+        Ok(())
+    }
+}
+
 #[derive(Default)]
 pub struct FrameTransform {
     pub extent: [u32; 3],
@@ -294,6 +363,37 @@ impl OverlayRenderer for FallbackRenderer {
         None
     }
 }
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WindowKind {
+    Mirror,
+    Ui,
+}
+
+const MOVE_ANIMATION_SECS: f32 = 0.6;
+
+pub struct MoveAnimation {
+    from: Affine3A,
+    start: Instant,
+}
+
+impl MoveAnimation {
+    fn progress(&self) -> f32 {
+        (self.start.elapsed().as_secs_f32() / MOVE_ANIMATION_SECS).min(1.)
+    }
+
+    fn lerp(&self, to: Affine3A, t: f32) -> Affine3A {
+        // smoothstep
+        let t = t * t * (3. - 2. * t);
+        let (from_scale, from_rot, from_pos) = self.from.to_scale_rotation_translation();
+        let (to_scale, to_rot, to_pos) = to.to_scale_rotation_translation();
+        Affine3A::from_scale_rotation_translation(
+            from_scale.lerp(to_scale, t),
+            from_rot.slerp(to_rot, t),
+            from_pos.lerp(to_pos, t),
+        )
+    }
+}
+
 // Boilerplate and dummies
 
 #[derive(Clone, Copy, Debug, Default)]
@@ -1,10 +1,14 @@
 use std::{
     cmp,
     collections::{BinaryHeap, VecDeque},
-    sync::atomic::{self, AtomicUsize},
+    sync::{
+        atomic::{self, AtomicUsize},
+        Arc,
+    },
     time::Instant,
 };
 
+use schemars::JsonSchema;
 use serde::Deserialize;
 
 use crate::state::AppState;
@@ -47,9 +51,19 @@ impl Ord for AppTask {
 
 pub enum SystemTask {
     ColorGain(ColorChannel, f32),
+    /// Set a color channel to an absolute gain
+    SetColorGain(ColorChannel, f32),
     ResetPlayspace,
     FixFloor,
     ShowHide,
+    /// Re-apply the config to live overlays
+    ApplyConfig,
+    SaveLayout(Arc<str>),
+    RestoreLayout(Arc<str>),
+    /// Redraw panels with the current theme
+    ApplyTheme,
+    /// Recreate the keyboard, e.g. after keyboard_scale was changed
+    RebuildKeyboard,
 }
 
 pub type OverlayTask = dyn FnOnce(&mut AppState, &mut OverlayState) + Send;
@@ -66,7 +80,7 @@ pub enum TaskType {
     WayVR(WayVRAction),
 }
 
-#[derive(Deserialize, Clone, Copy)]
+#[derive(Deserialize, Clone, Copy, JsonSchema)]
 pub enum ColorChannel {
     R,
     G,
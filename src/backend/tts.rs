@@ -0,0 +1,154 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    os::unix::net::UnixStream,
+    path::PathBuf,
+    process::Command,
+    sync::{mpsc, Arc},
+    time::Duration,
+};
+
+type SpeechRequest = (String, Option<Vec<Arc<str>>>);
+
+/// Speaks text on a background thread, through speech-dispatcher or a user-given command.
+#[derive(Default)]
+pub struct TextToSpeech {
+    sender: Option<mpsc::Sender<SpeechRequest>>,
+}
+
+impl TextToSpeech {
+    /// If a command is given, the text is appended to it as the last argument.
+    /// Otherwise, speech-dispatcher is used.
+    pub fn speak(&mut self, text: String, command: Option<Vec<Arc<str>>>) {
+        let sender = self.sender.get_or_insert_with(|| {
+            let (sender, receiver) = mpsc::channel();
+            let _ = std::thread::spawn(move || speech_thread(receiver));
+            sender
+        });
+
+        if sender.send((text, command)).is_err() {
+            log::error!("Text-to-speech thread has stopped.");
+            self.sender = None;
+        }
+    }
+}
+
+fn speech_thread(receiver: mpsc::Receiver<SpeechRequest>) {
+    let mut ssip: Option<SsipConnection> = None;
+
+    for (text, command) in receiver {
+        if let Some(command) = command {
+            run_command(&command, &text);
+            continue;
+        }
+
+        if ssip.is_none() {
+            ssip = SsipConnection::connect()
+                .map_err(|e| log::error!("Could not connect to speech-dispatcher: {:?}", e))
+                .ok();
+        }
+        if let Some(conn) = ssip.as_mut() {
+            if let Err(e) = conn.speak(&text) {
+                log::error!("speech-dispatcher: {:?}", e);
+                ssip = None;
+            }
+        }
+    }
+}
+
+fn run_command(command: &[Arc<str>], text: &str) {
+    let Some((program, args)) = command.split_first() else {
+        log::error!("notification_tts_command is empty");
+        return;
+    };
+
+    let result = Command::new(&**program)
+        .args(args.iter().map(|a| &**a))
+        .arg(text)
+        .status();
+    match result {
+        Ok(status) if !status.success() => {
+            log::warn!("Text-to-speech command exited with {}", status);
+        }
+        Err(e) => log::error!("Failed to run text-to-speech command: {:?}", e),
+        _ => {}
+    }
+}
+
+fn ssip_socket_path() -> PathBuf {
+    if let Ok(address) = std::env::var("SPEECHD_ADDRESS") {
+        if let Some(path) = address.strip_prefix("unix_socket:") {
+            return PathBuf::from(path);
+        }
+    }
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir)
+        .join("speech-dispatcher/speechd.sock")
+}
+
+/// Minimal client for the Speech Synthesis Interface Protocol of speech-dispatcher
+struct SsipConnection {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+}
+
+impl SsipConnection {
+    fn connect() -> anyhow::Result<Self> {
+        let path = ssip_socket_path();
+        let stream = match UnixStream::connect(&path) {
+            Ok(stream) => stream,
+            Err(_) => {
+                // same as what libspeechd does when autospawn is enabled
+                Command::new("speech-dispatcher").arg("--spawn").status()?;
+                UnixStream::connect(&path)?
+            }
+        };
+        stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+
+        let mut conn = Self {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+        };
+        conn.command("SET self CLIENT_NAME user:wlx-overlay-s:notifications")?;
+        log::info!(
+            "Connected to speech-dispatcher at {}",
+            path.to_string_lossy()
+        );
+        Ok(conn)
+    }
+
+    fn speak(&mut self, text: &str) -> anyhow::Result<()> {
+        self.command("SPEAK")?;
+
+        let mut data = String::new();
+        for line in text.lines() {
+            // a single dot ends the message, so leading dots are escaped
+            if line.starts_with('.') {
+                data.push('.');
+            }
+            data.push_str(line);
+            data.push_str("\r\n");
+        }
+        data.push('.');
+        self.command(&data)
+    }
+
+    fn command(&mut self, line: &str) -> anyhow::Result<()> {
+        self.writer.write_all(line.as_bytes())?;
+        self.writer.write_all(b"\r\n")?;
+
+        // replies are "NNN-text" lines, followed by a final "NNN text" line
+        loop {
+            let mut reply = String::new();
+            if self.reader.read_line(&mut reply)? == 0 {
+                anyhow::bail!("Connection closed");
+            }
+            match reply.as_bytes().get(3) {
+                Some(b' ') if reply.starts_with('2') => return Ok(()),
+                Some(b' ') => anyhow::bail!("{}", reply.trim()),
+                Some(b'-') => continue,
+                _ => anyhow::bail!("Malformed reply: {}", reply.trim()),
+            }
+        }
+    }
+}
@@ -73,7 +73,7 @@ impl PreviewState {
         let (swapchain, images) =
             create_swapchain(&state.graphics, surface.clone(), swapchain_size)?;
 
-        let mut canvas = modular_canvas(&config.size, &config.elements, state)?;
+        let mut canvas = modular_canvas(&config, state)?;
         canvas.init(state)?;
         let view = canvas.view().unwrap();
 
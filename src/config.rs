@@ -1,11 +1,20 @@
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+use crate::config_check;
+use crate::config_check::ConfigDiagnostic;
 use crate::config_io;
 use crate::config_io::get_conf_d_path;
 use crate::config_io::CONFIG_ROOT_PATH;
+#[cfg(feature = "osc")]
+use crate::gui::modular::button::ButtonAction;
 use crate::gui::modular::ModularUiConfig;
+use crate::gui::theme::theme_exists;
+use crate::gui::theme::Palette;
+use crate::gui::theme::DEFAULT_THEME;
 use crate::overlays::toast::DisplayMethod;
+use crate::overlays::toast::NotificationRule;
 use crate::overlays::toast::ToastTopic;
 use crate::state::LeftRight;
 use anyhow::bail;
@@ -18,6 +27,7 @@ use glam::Quat;
 use glam::Vec3A;
 use idmap::IdMap;
 use log::error;
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
 
@@ -134,6 +144,16 @@ fn def_osc_port() -> u16 {
     9000
 }
 
+#[cfg(feature = "osc")]
+fn def_osc_in_port() -> u16 {
+    9001
+}
+
+#[cfg(feature = "osc")]
+fn def_osc_actions() -> HashMap<Arc<str>, Vec<ButtonAction>> {
+    HashMap::new()
+}
+
 fn def_empty_vec_string() -> Vec<String> {
     Vec::new()
 }
@@ -175,20 +195,38 @@ fn def_toast_topics() -> IdMap<ToastTopic, DisplayMethod> {
     IdMap::new()
 }
 
+fn def_tts_topics() -> IdMap<ToastTopic, bool> {
+    IdMap::new()
+}
+
 fn def_font() -> Arc<str> {
     "LiberationSans:style=Bold".into()
 }
 
+fn def_notification_history_size() -> usize {
+    20
+}
+
+fn def_notification_max_toasts() -> usize {
+    3
+}
+
 fn def_max_height() -> u16 {
     1440
 }
 
-#[derive(Deserialize, Serialize)]
+fn def_theme() -> Arc<str> {
+    DEFAULT_THEME.into()
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
 pub struct GeneralConfig {
     #[serde(default = "def_watch_pos")]
+    #[schemars(with = "[f32; 3]")]
     pub watch_pos: Vec3A,
 
     #[serde(default = "def_watch_rot")]
+    #[schemars(with = "[f32; 4]")]
     pub watch_rot: Quat,
 
     #[serde(default = "def_left")]
@@ -206,15 +244,45 @@ pub struct GeneralConfig {
     #[serde(default = "def_true")]
     pub notifications_sound_enabled: bool,
 
+    /// Act as the desktop notification server if none is running or can be started.
+    /// A server started later on takes over.
+    #[serde(default = "def_true")]
+    pub notifications_server: bool,
+
     #[serde(default = "def_toast_topics")]
+    #[schemars(with = "HashMap<ToastTopic, DisplayMethod>")]
     pub notification_topics: IdMap<ToastTopic, DisplayMethod>,
 
+    /// Read out toasts of these topics with text-to-speech
+    #[serde(default = "def_tts_topics")]
+    #[schemars(with = "HashMap<ToastTopic, bool>")]
+    pub notification_tts_topics: IdMap<ToastTopic, bool>,
+
+    /// Used instead of speech-dispatcher. The text is passed as the last argument.
+    #[serde(default)]
+    pub notification_tts_command: Option<Vec<Arc<str>>>,
+
+    #[serde(default)]
+    pub notification_rules: Vec<NotificationRule>,
+
+    #[serde(default = "def_notification_history_size")]
+    pub notification_history_size: usize,
+
+    /// How many toasts can be on screen at once
+    #[serde(default = "def_notification_max_toasts")]
+    pub notification_max_toasts: usize,
+
     #[serde(default = "def_true")]
     pub keyboard_sound_enabled: bool,
 
     #[serde(default = "def_one")]
     pub keyboard_scale: f32,
 
+    /// Show a chatbox toggle on the keyboard, for typing into VRChat via OSC
+    #[cfg(feature = "osc")]
+    #[serde(default = "def_false")]
+    pub keyboard_chatbox: bool,
+
     #[serde(default = "def_one")]
     pub desktop_view_scale: f32,
 
@@ -230,6 +298,19 @@ pub struct GeneralConfig {
     #[serde(default = "def_osc_port")]
     pub osc_out_port: u16,
 
+    #[cfg(feature = "osc")]
+    #[serde(default = "crate::backend::osc::def_osc_out_params")]
+    pub osc_out_params: Vec<crate::backend::osc::OscParam>,
+
+    #[cfg(feature = "osc")]
+    #[serde(default = "def_osc_in_port")]
+    pub osc_in_port: u16,
+
+    #[cfg(feature = "osc")]
+    /// Actions to run when an OSC message is received on the given address
+    #[serde(default = "def_osc_actions", skip_serializing)]
+    pub osc_actions: HashMap<Arc<str>, Vec<ButtonAction>>,
+
     #[serde(default = "def_false")]
     pub upright_screen_fix: bool,
 
@@ -243,6 +324,7 @@ pub struct GeneralConfig {
     pub curve_values: AStrMap<f32>,
 
     #[serde(default = "def_transforms")]
+    #[schemars(with = "AStrMap<[f32; 12]>")]
     pub transform_values: AStrMap<Affine3A>,
 
     #[serde(default = "def_auto")]
@@ -310,28 +392,108 @@ pub struct GeneralConfig {
 
     #[serde(default = "def_timezones")]
     pub timezones: Vec<String>,
+
+    /// Allow Exec actions sent over the control socket or D-Bus
+    #[serde(default = "def_false")]
+    pub ipc_allow_exec: bool,
+
+    /// dark, light or one of the themes defined below
+    #[serde(default = "def_theme")]
+    pub theme: Arc<str>,
+
+    /// Colors not given here are taken from the dark theme
+    #[serde(default)]
+    pub themes: HashMap<Arc<str>, Palette>,
+
+    /// Overrides colors of the active theme on all panels
+    #[serde(default)]
+    pub palette: Palette,
 }
 
 impl GeneralConfig {
-    fn sanitize_range(name: &str, val: f32, from: f32, to: f32) {
-        if !val.is_normal() || val < from || val > to {
-            panic!(
-                "GeneralConfig: {} needs to be between {} and {}",
-                name, from, to
+    #[allow(clippy::too_many_arguments)]
+    fn sanitize_range(
+        name: &str,
+        val: &mut f32,
+        from: f32,
+        to: f32,
+        default: f32,
+        files: &[PathBuf],
+        diags: &mut Vec<ConfigDiagnostic>,
+    ) {
+        if !val.is_normal() || *val < from || *val > to {
+            diags.push(
+                ConfigDiagnostic::new(
+                    &file_setting(files, name),
+                    format!(
+                        "needs to be between {} and {}, using the default of {}",
+                        from, to, default
+                    ),
+                )
+                .with_field(name),
             );
+            *val = default;
         }
     }
 
+    /// Problems are reported, and broken files are skipped.
     pub fn load_from_disk() -> GeneralConfig {
-        let config = load_general();
-        config.post_load();
+        let mut diags = vec![];
+        let config = load_general(&mut diags);
+        diags.into_iter().for_each(config_check::report);
         config
     }
 
-    fn post_load(&self) {
-        GeneralConfig::sanitize_range("keyboard_scale", self.keyboard_scale, 0.05, 5.0);
-        GeneralConfig::sanitize_range("desktop_view_scale", self.desktop_view_scale, 0.05, 5.0);
+    /// Fails if any of the files has a problem.
+    pub fn try_load_from_disk() -> anyhow::Result<GeneralConfig> {
+        let mut diags = vec![];
+        let config = load_general(&mut diags);
+        match diags.len() {
+            0 => Ok(config),
+            1 => Err(diags.remove(0).into()),
+            _ => {
+                let lines: Vec<_> = diags.iter().map(ToString::to_string).collect();
+                bail!("{}", lines.join("\n"))
+            }
+        }
     }
+
+    /// `files` are the files that were merged, to tell which one a bad value came from
+    fn post_load(&mut self, files: &[PathBuf], diags: &mut Vec<ConfigDiagnostic>) {
+        GeneralConfig::sanitize_range(
+            "keyboard_scale",
+            &mut self.keyboard_scale,
+            0.05,
+            5.0,
+            def_one(),
+            files,
+            diags,
+        );
+        GeneralConfig::sanitize_range(
+            "desktop_view_scale",
+            &mut self.desktop_view_scale,
+            0.05,
+            5.0,
+            def_one(),
+            files,
+            diags,
+        );
+
+        if !theme_exists(self, &self.theme) {
+            diags.push(
+                ConfigDiagnostic::new(
+                    &file_setting(files, "theme"),
+                    format!("unknown theme {}, using {}", self.theme, DEFAULT_THEME),
+                )
+                .with_field("theme"),
+            );
+            self.theme = def_theme();
+        }
+    }
+}
+
+fn def_general() -> GeneralConfig {
+    serde_yaml::from_str("{}").unwrap() // want panic
 }
 
 const FALLBACKS: [&str; 5] = [
@@ -361,25 +523,39 @@ pub enum ConfigType {
     WayVR,
 }
 
+/// Problems with the user's file are reported, then the built-in defaults are used.
 pub fn load_known_yaml<T>(config_type: ConfigType) -> T
 where
     T: for<'de> Deserialize<'de>,
 {
-    let fallback = FALLBACKS[config_type as usize];
-    let file_name = FILES[config_type as usize];
-    let maybe_override = config_io::load(file_name);
-
-    for yaml in [maybe_override.as_deref(), Some(fallback)].iter().flatten() {
-        match serde_yaml::from_str::<T>(yaml) {
-            Ok(d) => return d,
-            Err(e) => {
-                error!("Failed to parse {}, falling back to defaults.", file_name);
-                error!("{}", e);
-            }
+    match try_load_known_yaml(config_type) {
+        Ok(d) => d,
+        Err(e) => {
+            let file_name = FILES[config_type as usize];
+            config_check::report(ConfigDiagnostic::from_error(file_name, e));
+            load_builtin_yaml(config_type)
         }
     }
-    // can only get here if internal fallback is broken
-    panic!("No usable config found.");
+}
+
+/// Like `load_known_yaml`, but returns an error instead of falling back to defaults.
+pub fn try_load_known_yaml<T>(config_type: ConfigType) -> anyhow::Result<T>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    let file_name = FILES[config_type as usize];
+    let yaml =
+        config_io::load(file_name).unwrap_or_else(|| FALLBACKS[config_type as usize].to_string());
+
+    Ok(config_check::parse_yaml(file_name, &yaml)?)
+}
+
+pub fn load_builtin_yaml<T>(config_type: ConfigType) -> T
+where
+    T: for<'de> Deserialize<'de>,
+{
+    // can only fail if internal fallback is broken
+    serde_yaml::from_str(FALLBACKS[config_type as usize]).unwrap() // want panic
 }
 
 pub fn load_custom_ui(name: &str) -> anyhow::Result<ModularUiConfig> {
@@ -387,11 +563,11 @@ pub fn load_custom_ui(name: &str) -> anyhow::Result<ModularUiConfig> {
     let Some(yaml_data) = config_io::load(&filename) else {
         bail!("Could not read file at {}", &filename);
     };
-    Ok(serde_yaml::from_str(&yaml_data)?)
+    Ok(config_check::parse_yaml(&filename, &yaml_data)?)
 }
 
-pub fn load_general() -> GeneralConfig {
-    let mut settings_builder = Config::builder();
+fn general_config_files() -> Vec<PathBuf> {
+    let mut files = vec![];
 
     // Add files from conf.d directory
     let path_conf_d = get_conf_d_path();
@@ -399,15 +575,14 @@ pub fn load_general() -> GeneralConfig {
     for mut base_conf in [CONFIG_ROOT_PATH.clone(), path_conf_d.clone()] {
         base_conf.push("config.yaml");
         if base_conf.exists() {
-            log::info!("Loading config file: {}", base_conf.to_string_lossy());
-            settings_builder = settings_builder.add_source(File::from(base_conf));
+            files.push(base_conf);
         }
     }
 
     if let Ok(paths_unsorted) = std::fs::read_dir(path_conf_d) {
         let mut paths: Vec<_> = paths_unsorted
             .filter_map(|r| match r {
-                Ok(entry) => Some(entry),
+                Ok(entry) => Some(entry.path()),
                 Err(e) => {
                     error!("Failed to read conf.d directory: {}", e);
                     None
@@ -415,23 +590,98 @@ pub fn load_general() -> GeneralConfig {
             })
             .collect();
         // Sort paths alphabetically
-        paths.sort_by_key(|dir| dir.path());
-        for path in paths {
-            log::info!("Loading config file: {}", path.path().to_string_lossy());
-            settings_builder = settings_builder.add_source(File::from(path.path()));
+        paths.sort();
+        files.extend(paths);
+    }
+
+    // The active profile goes on top of conf.d
+    if let Some(path_profile) = config_io::get_profile_path() {
+        match std::fs::read_dir(&path_profile) {
+            Ok(entries) => {
+                let mut paths: Vec<_> = entries.filter_map(|r| r.ok().map(|e| e.path())).collect();
+                paths.sort();
+                files.extend(paths);
+            }
+            Err(e) => error!(
+                "Failed to read profile directory {}: {}",
+                path_profile.to_string_lossy(),
+                e
+            ),
         }
     }
 
-    match settings_builder.build() {
-        Ok(settings) => match settings.try_deserialize::<GeneralConfig>() {
-            Ok(config) => config,
-            Err(e) => {
-                panic!("Failed to deserialize settings: {}", e);
+    files
+}
+
+/// Files that can not be read as a config are left out, so the rest of the config still applies.
+fn load_general(diags: &mut Vec<ConfigDiagnostic>) -> GeneralConfig {
+    let mut settings_builder = Config::builder();
+    let mut loaded = vec![];
+
+    for path in general_config_files() {
+        if let Err(diag) = config_check::check_general_file(&path) {
+            // the strict check has the better message, but merging also coerces
+            // values such as "1.0" or 1 for floats, which older configs rely on
+            if !loads_alone(&path) {
+                diags.push(diag);
+                continue;
             }
-        },
+        }
+        log::info!("Loading config file: {}", path.to_string_lossy());
+        settings_builder = settings_builder.add_source(File::from(path.as_path()));
+        loaded.push(path);
+    }
+
+    let mut config = match settings_builder
+        .build()
+        .and_then(|settings| settings.try_deserialize::<GeneralConfig>())
+    {
+        Ok(config) => config,
         Err(e) => {
-            panic!("Failed to build settings: {}", e);
+            diags.push(ConfigDiagnostic::new(
+                "config.yaml",
+                format!("{}, using defaults", e),
+            ));
+            def_general()
         }
+    };
+    config.post_load(&loaded, diags);
+    config
+}
+
+fn loads_alone(path: &Path) -> bool {
+    Config::builder()
+        .add_source(File::from(path))
+        .build()
+        .and_then(|settings| settings.try_deserialize::<GeneralConfig>())
+        .is_ok()
+}
+
+/// The general config file that sets the given field, for diagnostics
+pub fn general_file_setting(field: &str) -> String {
+    file_setting(&general_config_files(), field)
+}
+
+/// The last of the merged files that sets the given field
+fn file_setting(files: &[PathBuf], field: &str) -> String {
+    files
+        .iter()
+        .rev()
+        .find(|path| file_sets(path, field))
+        .map(|path| path.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "config.yaml".into())
+}
+
+fn file_sets(path: &Path, field: &str) -> bool {
+    let Ok(text) = std::fs::read_to_string(path) else {
+        return false;
+    };
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("yaml" | "yml" | "json") => serde_yaml::from_str::<serde_yaml::Value>(&text)
+            .is_ok_and(|value| value.get(field).is_some()),
+        Some("json5") => serde_json5::from_str::<serde_json::Value>(&text)
+            .is_ok_and(|value| value.get(field).is_some()),
+        _ => false,
     }
 }
 
@@ -449,11 +699,15 @@ pub struct AutoSettings {
     pub realign_on_showhide: bool,
     pub allow_sliding: bool,
     pub space_drag_multiplier: f32,
+    pub theme: Arc<str>,
 }
 
+pub const SAVED_SETTINGS_FILE: &str = "zz-saved-config.json5";
+pub const SAVED_STATE_FILE: &str = "zz-saved-state.json5";
+
 fn get_settings_path() -> PathBuf {
-    let mut path = config_io::get_conf_d_path();
-    path.push("zz-saved-config.json5");
+    let mut path = config_io::get_saved_config_path();
+    path.push(SAVED_SETTINGS_FILE);
     path
 }
 pub fn save_settings(config: &GeneralConfig) -> anyhow::Result<()> {
@@ -468,6 +722,7 @@ pub fn save_settings(config: &GeneralConfig) -> anyhow::Result<()> {
         realign_on_showhide: config.realign_on_showhide,
         allow_sliding: config.allow_sliding,
         space_drag_multiplier: config.space_drag_multiplier,
+        theme: config.theme.clone(),
     };
 
     let json = serde_json::to_string_pretty(&conf).unwrap(); // want panic
@@ -486,8 +741,8 @@ pub struct AutoState {
 }
 
 fn get_state_path() -> PathBuf {
-    let mut path = config_io::get_conf_d_path();
-    path.push("zz-saved-state.json5");
+    let mut path = config_io::get_saved_config_path();
+    path.push(SAVED_STATE_FILE);
     path
 }
 
@@ -0,0 +1,243 @@
+use std::{fmt, path::Path, sync::Mutex};
+
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+
+use crate::{
+    config::{load_custom_ui, try_load_known_yaml, ConfigType, GeneralConfig},
+    config_io::{self, CONFIG_ROOT_PATH},
+    gui::modular::{component::expand_elements, ModularUiConfig},
+    overlays::toast::ToastRule,
+};
+
+/// A problem found in a config file, pointing to where it was found if possible.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigDiagnostic {
+    pub file: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub field: Option<String>,
+    pub message: String,
+}
+
+impl ConfigDiagnostic {
+    pub fn new(file: &str, message: impl Into<String>) -> Self {
+        Self {
+            file: file.to_string(),
+            line: None,
+            column: None,
+            field: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn with_field(mut self, field: impl Into<String>) -> Self {
+        self.field = Some(field.into());
+        self
+    }
+
+    fn with_location(mut self, line: usize, column: usize) -> Self {
+        // 0 means unknown
+        self.line = (line > 0).then_some(line);
+        self.column = (column > 0).then_some(column);
+        self
+    }
+
+    /// Keeps the details if the error came from this module.
+    pub fn from_error(file: &str, error: anyhow::Error) -> Self {
+        match error.downcast::<ConfigDiagnostic>() {
+            Ok(diag) => diag,
+            Err(e) => Self::new(file, e.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for ConfigDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.file)?;
+        if let Some(line) = self.line {
+            write!(f, ":{}", line)?;
+            if let Some(column) = self.column {
+                write!(f, ":{}", column)?;
+            }
+        }
+        if let Some(field) = self.field.as_ref() {
+            write!(f, ": {}", field)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl std::error::Error for ConfigDiagnostic {}
+
+static DIAGNOSTICS: Lazy<Mutex<Vec<ConfigDiagnostic>>> = Lazy::new(|| Mutex::new(vec![]));
+
+/// Logs a problem and queues it to be shown as a toast.
+pub fn report(diag: ConfigDiagnostic) {
+    log::error!("{}", diag);
+    if let Ok(mut diags) = DIAGNOSTICS.lock() {
+        if !diags.contains(&diag) {
+            diags.push(diag);
+        }
+    }
+}
+
+/// Takes all problems reported since the last call.
+pub fn take_reported() -> Vec<ConfigDiagnostic> {
+    DIAGNOSTICS
+        .lock()
+        .map(|mut diags| std::mem::take(&mut *diags))
+        .unwrap_or_default()
+}
+
+fn field_path(path: &serde_path_to_error::Path) -> Option<String> {
+    let path = path.to_string();
+    (path != ".").then_some(path)
+}
+
+pub fn parse_yaml<T>(file: &str, yaml: &str) -> Result<T, ConfigDiagnostic>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    serde_path_to_error::deserialize(serde_yaml::Deserializer::from_str(yaml)).map_err(|e| {
+        let field = field_path(e.path());
+        let inner = e.into_inner();
+        let mut diag = ConfigDiagnostic::new(file, inner.to_string());
+        if let Some(location) = inner.location() {
+            diag = diag.with_location(location.line(), location.column());
+        }
+        diag.field = field;
+        diag
+    })
+}
+
+pub fn parse_json<T>(file: &str, json: &str) -> Result<T, ConfigDiagnostic>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    let mut de = serde_json::Deserializer::from_str(json);
+    serde_path_to_error::deserialize(&mut de).map_err(|e| {
+        let field = field_path(e.path());
+        let inner = e.into_inner();
+        let mut diag = ConfigDiagnostic::new(file, inner.to_string())
+            .with_location(inner.line(), inner.column());
+        diag.field = field;
+        diag
+    })
+}
+
+/// Checks a single file that is merged into `GeneralConfig`.
+pub fn check_general_file(path: &Path) -> Result<(), ConfigDiagnostic> {
+    let file = path.to_string_lossy();
+    let text =
+        std::fs::read_to_string(path).map_err(|e| ConfigDiagnostic::new(&file, e.to_string()))?;
+
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("yaml" | "yml") => parse_yaml::<GeneralConfig>(&file, &text).map(|_| ()),
+        Some("json") => parse_json::<GeneralConfig>(&file, &text).map(|_| ()),
+        Some("json5") => serde_json5::from_str::<GeneralConfig>(&text)
+            .map(|_| ())
+            .map_err(|e| ConfigDiagnostic::new(&file, e.to_string())),
+        // other formats are only checked when merged
+        _ => Ok(()),
+    }
+}
+
+/// Loads every config file without falling back to defaults, collecting all problems.
+pub fn check_all() -> Vec<ConfigDiagnostic> {
+    let _ = take_reported();
+    let mut diags = vec![];
+
+    let config = GeneralConfig::load_from_disk();
+    let _ = ToastRule::compile_all(&config.notification_rules);
+    diags.extend(take_reported());
+
+    if let Err(e) = crate::overlays::keyboard::check_layout() {
+        diags.push(ConfigDiagnostic::from_error("keyboard.yaml", e));
+    }
+
+    for (file, config_type) in [
+        ("watch.yaml", ConfigType::Watch),
+        ("settings.yaml", ConfigType::Settings),
+        ("anchor.yaml", ConfigType::Anchor),
+    ] {
+        let result = try_load_known_yaml::<ModularUiConfig>(config_type)
+            .and_then(|config| expand_elements(&config));
+        if let Err(e) = result {
+            diags.push(ConfigDiagnostic::from_error(file, e));
+        }
+    }
+
+    #[cfg(feature = "wayvr")]
+    if let Err(e) = crate::config_wayvr::try_load_wayvr() {
+        diags.push(ConfigDiagnostic::from_error("wayvr.yaml", e));
+    }
+
+    // everything else in the config root is a custom panel or the toast template,
+    // unless it only holds components for others to include
+    let known = ["keyboard", "watch", "settings", "anchor", "wayvr", "config"];
+    if let Ok(entries) = std::fs::read_dir(CONFIG_ROOT_PATH.as_path()) {
+        let mut files: Vec<_> = entries
+            .filter_map(|e| e.ok())
+            .filter_map(|e| e.file_name().into_string().ok())
+            .filter(|name| name.ends_with(".yaml"))
+            .map(|name| {
+                // unreadable files are checked like panels, to report why
+                let probe = probe_ui_file(&name).unwrap_or((vec![], true));
+                (name, probe)
+            })
+            .collect();
+        files.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let included: Vec<&str> = files
+            .iter()
+            .flat_map(|(_, (include, _))| include.iter().map(String::as_str))
+            .collect();
+
+        for (file, (_, has_elements)) in files.iter() {
+            let name = file.trim_end_matches(".yaml");
+            if known.contains(&name) || !has_elements || included.contains(&file.as_str()) {
+                continue;
+            }
+            if let Err(e) = load_custom_ui(name).and_then(|config| expand_elements(&config)) {
+                diags.push(ConfigDiagnostic::from_error(file, e));
+            }
+        }
+    }
+
+    diags
+}
+
+/// The `include:` list of a UI file and whether it has `elements:`, without checking the rest
+fn probe_ui_file(file: &str) -> Option<(Vec<String>, bool)> {
+    let yaml = config_io::load(file)?;
+    let value: serde_yaml::Value = serde_yaml::from_str(&yaml).ok()?;
+    let include = value
+        .get("include")
+        .and_then(|v| v.as_sequence())
+        .map(|seq| {
+            seq.iter()
+                .filter_map(|v| v.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+    Some((include, value.get("elements").is_some()))
+}
+
+/// Entry point for `--check-config`. Returns false if any problems were found.
+pub fn check_config_cli() -> bool {
+    println!("Checking config in {}", CONFIG_ROOT_PATH.to_string_lossy());
+
+    let diags = check_all();
+    for diag in diags.iter() {
+        println!("{}", diag);
+    }
+
+    if diags.is_empty() {
+        println!("No problems found.");
+        true
+    } else {
+        println!("{} problem(s) found.", diags.len());
+        false
+    }
+}
@@ -3,6 +3,7 @@ use once_cell::sync::Lazy;
 use std::{
     fs::{self, create_dir},
     path::PathBuf,
+    sync::{Arc, RwLock},
 };
 
 const FALLBACK_CONFIG_PATH: &str = "/tmp/wlxoverlay";
@@ -27,6 +28,48 @@ pub fn get_conf_d_path() -> PathBuf {
     config_root
 }
 
+static ACTIVE_PROFILE: RwLock<Option<Arc<str>>> = RwLock::new(None);
+
+pub fn get_profiles_path() -> PathBuf {
+    let mut config_root = CONFIG_ROOT_PATH.clone();
+    config_root.push("profiles");
+    config_root
+}
+
+/// Directory of the active profile, merged after conf.d
+pub fn get_profile_path() -> Option<PathBuf> {
+    get_active_profile().map(|name| get_profiles_path().join(name.as_ref()))
+}
+
+pub fn get_active_profile() -> Option<Arc<str>> {
+    ACTIVE_PROFILE.read().ok().and_then(|p| p.clone())
+}
+
+pub fn set_active_profile(name: Option<Arc<str>>) {
+    if let Ok(mut profile) = ACTIVE_PROFILE.write() {
+        *profile = name;
+    }
+}
+
+pub fn list_profiles() -> Vec<Arc<str>> {
+    let Ok(entries) = fs::read_dir(get_profiles_path()) else {
+        return vec![];
+    };
+    let mut profiles: Vec<Arc<str>> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_dir())
+        .filter_map(|e| e.file_name().into_string().ok())
+        .map(Arc::from)
+        .collect();
+    profiles.sort();
+    profiles
+}
+
+/// Where settings saved at runtime go: the active profile, or conf.d
+pub fn get_saved_config_path() -> PathBuf {
+    get_profile_path().unwrap_or_else(get_conf_d_path)
+}
+
 // Make sure config directory is present and return root config path
 pub fn ensure_config_root() -> PathBuf {
     let path = CONFIG_ROOT_PATH.clone();
@@ -0,0 +1,43 @@
+use clap::ValueEnum;
+use schemars::{schema::RootSchema, schema_for};
+
+use crate::{config::GeneralConfig, gui::modular::ModularUiConfig, overlays::keyboard::Layout};
+
+/// Config formats that `--dump-schema` can print.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum SchemaKind {
+    /// config.yaml and the files in conf.d
+    General,
+    /// watch.yaml, settings.yaml, anchor.yaml and custom panels
+    Ui,
+    /// keyboard.yaml
+    Keyboard,
+    /// wayvr.yaml
+    Wayvr,
+    /// openxr_actions.json5
+    OpenxrActions,
+}
+
+fn schema_for_kind(kind: SchemaKind) -> anyhow::Result<RootSchema> {
+    Ok(match kind {
+        SchemaKind::General => schema_for!(GeneralConfig),
+        SchemaKind::Ui => schema_for!(ModularUiConfig),
+        SchemaKind::Keyboard => schema_for!(Layout),
+        #[cfg(feature = "wayvr")]
+        SchemaKind::Wayvr => schema_for!(crate::config_wayvr::WayVRConfig),
+        #[cfg(not(feature = "wayvr"))]
+        SchemaKind::Wayvr => anyhow::bail!("Built without the wayvr feature"),
+        #[cfg(feature = "openxr")]
+        SchemaKind::OpenxrActions => crate::backend::openxr::action_profiles_schema(),
+        #[cfg(not(feature = "openxr"))]
+        SchemaKind::OpenxrActions => anyhow::bail!("Built without the openxr feature"),
+    })
+}
+
+/// Entry point for `--dump-schema`. Prints a JSON Schema that editors can use to
+/// complete and validate the config, e.g. via a `# yaml-language-server: $schema=` comment.
+pub fn dump_schema_cli(kind: SchemaKind) -> anyhow::Result<()> {
+    let schema = schema_for_kind(kind)?;
+    println!("{}", serde_json::to_string_pretty(&schema)?);
+    Ok(())
+}
@@ -8,6 +8,7 @@ use std::{
     sync::Arc,
 };
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::{
@@ -16,12 +17,13 @@ use crate::{
         task::{TaskContainer, TaskType},
         wayvr,
     },
-    config::{load_known_yaml, ConfigType},
+    config::{load_builtin_yaml, try_load_known_yaml, ConfigType},
+    config_check::{self, ConfigDiagnostic},
     overlays::wayvr::{WayVRAction, WayVRState},
 };
 
 // Flat version of RelativeTo
-#[derive(Clone, Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize, JsonSchema)]
 pub enum AttachTo {
     None,
     HandLeft,
@@ -42,13 +44,13 @@ impl AttachTo {
     }
 }
 
-#[derive(Clone, Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize, JsonSchema)]
 pub struct Rotation {
     pub axis: [f32; 3],
     pub angle: f32,
 }
 
-#[derive(Clone, Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize, JsonSchema)]
 pub struct WayVRAppEntry {
     pub name: String,
     pub target_display: String,
@@ -58,7 +60,7 @@ pub struct WayVRAppEntry {
     pub shown_at_start: Option<bool>,
 }
 
-#[derive(Clone, Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize, JsonSchema)]
 pub struct WayVRDisplay {
     pub width: u32,
     pub height: u32,
@@ -69,7 +71,7 @@ pub struct WayVRDisplay {
     pub primary: Option<bool>,
 }
 
-#[derive(Clone, Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize, JsonSchema)]
 pub struct WayVRCatalog {
     pub apps: Vec<WayVRAppEntry>,
 }
@@ -96,7 +98,7 @@ fn def_keyboard_repeat_rate() -> u32 {
     50
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, JsonSchema)]
 pub struct WayVRConfig {
     pub version: u32,
     pub run_compositor_at_start: bool,
@@ -193,9 +195,24 @@ impl WayVRConfig {
 }
 
 pub fn load_wayvr() -> WayVRConfig {
-    let config = load_known_yaml::<WayVRConfig>(ConfigType::WayVR);
+    match try_load_wayvr() {
+        Ok(config) => config,
+        Err(e) => {
+            config_check::report(ConfigDiagnostic::from_error("wayvr.yaml", e));
+            load_builtin_yaml::<WayVRConfig>(ConfigType::WayVR)
+        }
+    }
+}
+
+pub fn try_load_wayvr() -> anyhow::Result<WayVRConfig> {
+    let config = try_load_known_yaml::<WayVRConfig>(ConfigType::WayVR)?;
     if config.version != 1 {
-        panic!("WayVR config version {} is not supported", config.version);
+        return Err(ConfigDiagnostic::new(
+            "wayvr.yaml",
+            format!("WayVR config version {} is not supported", config.version),
+        )
+        .with_field("version")
+        .into());
     }
-    config
+    Ok(config)
 }
@@ -0,0 +1,111 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    os::unix::net::UnixStream,
+    time::Duration,
+};
+
+use clap::Subcommand;
+use serde_json::json;
+
+use crate::{
+    backend::ipc::{socket_path, IpcResponse},
+    gui::modular::button::ButtonAction,
+};
+
+#[derive(Subcommand, Debug)]
+pub enum CtlCommand {
+    /// List overlays of the running instance
+    List,
+    /// Show an overlay by name
+    Show { overlay: String },
+    /// Hide an overlay by name
+    Hide { overlay: String },
+    /// Toggle the working set, same as the Show/Hide binding
+    ShowHide,
+    /// Display a toast message
+    Toast {
+        message: String,
+        #[arg(long)]
+        body: Option<String>,
+        #[arg(long)]
+        seconds: Option<f32>,
+    },
+    /// Run a button action, given as YAML, e.g. '{type: Watch, action: SwitchHands}'
+    Action { action: String },
+    /// Reset the playspace offset
+    ResetPlayspace,
+    /// Fix the floor height
+    FixFloor,
+}
+
+pub fn ctl_run(command: &CtlCommand) -> anyhow::Result<()> {
+    let request = match command {
+        CtlCommand::List => json!({ "command": "list_overlays" }),
+        CtlCommand::Show { overlay } => {
+            json!({ "command": "set_visible", "target": overlay, "visible": true })
+        }
+        CtlCommand::Hide { overlay } => {
+            json!({ "command": "set_visible", "target": overlay, "visible": false })
+        }
+        CtlCommand::ShowHide => json!({ "command": "show_hide" }),
+        CtlCommand::Toast {
+            message,
+            body,
+            seconds,
+        } => json!({
+            "command": "action",
+            "action": { "type": "Toast", "message": message, "body": body, "seconds": seconds },
+        }),
+        CtlCommand::Action { action } => {
+            // parse locally first, for a meaningful error message
+            serde_yaml::from_str::<ButtonAction>(action)
+                .map_err(|e| anyhow::anyhow!("Invalid action: {}", e))?;
+            let action: serde_yaml::Value = serde_yaml::from_str(action)?;
+            let action = serde_json::to_value(action)?;
+            json!({ "command": "action", "action": action })
+        }
+        CtlCommand::ResetPlayspace => json!({ "command": "reset_playspace" }),
+        CtlCommand::FixFloor => json!({ "command": "fix_floor" }),
+    };
+
+    match send_request(&request)? {
+        IpcResponse::Ok => {}
+        IpcResponse::Overlays { overlays } => {
+            for o in overlays {
+                println!(
+                    "{:>3} {:<24} {:<7} [{:.3}, {:.3}, {:.3}] scale {:.3}",
+                    o.id,
+                    o.name,
+                    if o.visible { "visible" } else { "hidden" },
+                    o.position.x,
+                    o.position.y,
+                    o.position.z,
+                    o.scale,
+                );
+            }
+        }
+        IpcResponse::Error { message } => anyhow::bail!(message),
+    }
+
+    Ok(())
+}
+
+fn send_request(request: &serde_json::Value) -> anyhow::Result<IpcResponse> {
+    let path = socket_path()?;
+    let mut stream = UnixStream::connect(&path).map_err(|e| {
+        anyhow::anyhow!(
+            "Could not connect to {}, is WlxOverlay-S running? {}",
+            path.to_string_lossy(),
+            e
+        )
+    })?;
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+
+    let mut line = serde_json::to_string(request)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())?;
+
+    let mut response = String::new();
+    BufReader::new(stream).read_line(&mut response)?;
+    Ok(serde_json::from_str(&response)?)
+}
@@ -546,6 +546,102 @@ impl WlxGraphics {
         Ok(Arc::new(me))
     }
 
+    #[cfg(feature = "headless")]
+    pub fn new_headless() -> anyhow::Result<Arc<Self>> {
+        use vulkano::{device::physical::PhysicalDeviceType, instance::InstanceCreateFlags};
+
+        let vk_instance_extensions = InstanceExtensions {
+            khr_get_physical_device_properties2: true,
+            ..InstanceExtensions::empty()
+        };
+        log::debug!("Instance exts for headless: {:?}", &vk_instance_extensions);
+
+        let instance = Instance::new(
+            get_vulkan_library().clone(),
+            InstanceCreateInfo {
+                flags: InstanceCreateFlags::ENUMERATE_PORTABILITY,
+                enabled_extensions: vk_instance_extensions,
+                ..Default::default()
+            },
+        )?;
+
+        let (physical_device, queue_family_index) = instance
+            .enumerate_physical_devices()?
+            .filter_map(|p| {
+                p.queue_family_properties()
+                    .iter()
+                    .position(|q| q.queue_flags.intersects(QueueFlags::GRAPHICS))
+                    .map(|i| (p, i as u32))
+            })
+            // software rasterizers (lavapipe, swiftshader) are preferred,
+            // so that results do not depend on the GPU of the machine
+            .min_by_key(|(p, _)| match p.properties().device_type {
+                PhysicalDeviceType::Cpu => 0,
+                PhysicalDeviceType::VirtualGpu => 1,
+                PhysicalDeviceType::IntegratedGpu => 2,
+                PhysicalDeviceType::DiscreteGpu => 3,
+                PhysicalDeviceType::Other => 4,
+                _ => 5,
+            })
+            .ok_or_else(|| anyhow!("no suitable physical device found"))?;
+
+        log::info!(
+            "Using vkPhysicalDevice: {}",
+            physical_device.properties().device_name,
+        );
+
+        let (device, mut queues) = Device::new(
+            physical_device,
+            DeviceCreateInfo {
+                enabled_extensions: DeviceExtensions::empty(),
+                enabled_features: Features {
+                    dynamic_rendering: true,
+                    ..Features::empty()
+                },
+                queue_create_infos: vec![QueueCreateInfo {
+                    queue_family_index,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+        )?;
+
+        let queue = queues
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("no GPU queues available"))?;
+
+        let memory_allocator = memory_allocator(device.clone());
+        let command_buffer_allocator = Arc::new(StandardCommandBufferAllocator::new(
+            device.clone(),
+            StandardCommandBufferAllocatorCreateInfo {
+                secondary_buffer_count: 32,
+                ..Default::default()
+            },
+        ));
+        let descriptor_set_allocator = Arc::new(StandardDescriptorSetAllocator::new(
+            device.clone(),
+            Default::default(),
+        ));
+
+        let (quad_verts, quad_indices) = Self::default_quad(memory_allocator.clone())?;
+
+        let me = Self {
+            instance,
+            device,
+            queue,
+            memory_allocator,
+            native_format: Format::R8G8B8A8_UNORM,
+            texture_filtering: Filter::Linear,
+            command_buffer_allocator,
+            descriptor_set_allocator,
+            quad_indices,
+            quad_verts,
+            shared_shaders: RwLock::new(HashMap::new()),
+        };
+
+        Ok(Arc::new(me))
+    }
+
     #[cfg(feature = "uidev")]
     pub fn new_window() -> anyhow::Result<(
         Arc<Self>,
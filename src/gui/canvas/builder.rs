@@ -153,6 +153,35 @@ impl<D, S> CanvasBuilder<D, S> {
         &mut self.canvas.controls[idx]
     }
 
+    // Creates a slider with fg_color, bg_color, font_size inherited from the canvas
+    pub fn slider(
+        &mut self,
+        x: f32,
+        y: f32,
+        w: f32,
+        h: f32,
+        radius: f32,
+        fill_color: GuiColor,
+    ) -> &mut Control<D, S> {
+        let idx = self.canvas.controls.len();
+
+        self.canvas.interactive_set_idx(x, y, w, h, idx);
+        self.canvas.controls.push(Control {
+            rect: Rect { x, y, w, h },
+            corner_radius: radius,
+            fg_color: self.fg_color,
+            bg_color: self.bg_color,
+            fill_color,
+            size: self.font_size,
+            on_render_bg: Some(Control::render_slider_bg),
+            on_render_fg: Some(Control::render_text_centered),
+            on_render_hl: Some(Control::render_highlight),
+            ..Control::new()
+        });
+
+        &mut self.canvas.controls[idx]
+    }
+
     pub fn key_button(
         &mut self,
         x: f32,
@@ -1,4 +1,4 @@
-use glam::Vec4;
+use glam::{Vec2, Vec4};
 use std::sync::Arc;
 use vulkano::image::view::ImageView;
 
@@ -25,6 +25,9 @@ pub(crate) struct Control<D, S> {
     pub corner_radius: f32,
     pub fg_color: GuiColor,
     pub bg_color: GuiColor,
+    /// Portion of the width that render_slider_bg fills with fill_color
+    pub(super) fill: f32,
+    pub fill_color: GuiColor,
     pub text: Arc<str>,
     pub size: isize,
     pub sprite: Option<Arc<ImageView>>,
@@ -35,6 +38,9 @@ pub(crate) struct Control<D, S> {
     pub on_press: Option<fn(&mut Self, &mut D, &mut AppState, PointerMode)>,
     pub on_release: Option<fn(&mut Self, &mut D, &mut AppState)>,
     pub on_scroll: Option<fn(&mut Self, &mut D, &mut AppState, f32)>,
+    /// Called on press and while the pointer moves with the button held.
+    /// The position is relative to the rect, from (0, 0) to (1, 1).
+    pub on_drag: Option<fn(&mut Self, &mut D, &mut AppState, Vec2)>,
     pub test_highlight: Option<fn(&Self, &mut D, &mut AppState) -> Option<Vec4>>,
 
     pub(super) on_render_bg: Option<ControlRenderer<D, S>>,
@@ -54,6 +60,8 @@ impl<D, S> Control<D, S> {
             corner_radius: 0.,
             fg_color: Vec4::ONE,
             bg_color: Vec4::ZERO,
+            fill: 0.,
+            fill_color: Vec4::ONE,
             text: Arc::from(""),
             sprite: None,
             sprite_st: Vec4::new(1., 1., 0., 0.),
@@ -68,6 +76,7 @@ impl<D, S> Control<D, S> {
             on_press: None,
             on_release: None,
             on_scroll: None,
+            on_drag: None,
         }
     }
 
@@ -104,6 +113,16 @@ impl<D, S> Control<D, S> {
         self.dirty = true;
     }
 
+    #[inline(always)]
+    pub fn set_fill(&mut self, fill: f32) {
+        let fill = fill.clamp(0., 1.);
+        if self.fill == fill {
+            return;
+        }
+        self.fill = fill;
+        self.dirty = true;
+    }
+
     pub fn render_rounded_rect(
         &self,
         canvas: &CanvasData<D>,
@@ -195,6 +214,54 @@ impl<D, S> Control<D, S> {
         cmd_buffer.run_ref(&pass)
     }
 
+    pub(super) fn render_slider_bg(
+        &self,
+        canvas: &CanvasData<D>,
+        app: &mut AppState,
+        cmd_buffer: &mut WlxCommandBuffer,
+    ) -> anyhow::Result<()> {
+        self.render_rounded_rect(canvas, app, cmd_buffer)?;
+
+        let fill_w = self.rect.w * self.fill;
+        if fill_w < 1. {
+            return Ok(());
+        }
+
+        let vertex_buffer = canvas.graphics.upload_verts(
+            canvas.width as _,
+            canvas.height as _,
+            self.rect.x,
+            self.rect.y,
+            fill_w,
+            self.rect.h,
+        )?;
+
+        let clamped_radius = self.corner_radius.min(fill_w / 2.0).min(self.rect.h / 2.0);
+
+        let skew_radius = [clamped_radius / fill_w, clamped_radius / self.rect.h];
+
+        let set0 = canvas.pipeline_bg_color.uniform_buffer(
+            0,
+            vec![
+                self.fill_color.x,
+                self.fill_color.y,
+                self.fill_color.z,
+                self.fill_color.w,
+                skew_radius[0],
+                skew_radius[1],
+            ],
+        )?;
+
+        let pass = canvas.pipeline_bg_color.create_pass(
+            [canvas.width as _, canvas.height as _],
+            vertex_buffer,
+            canvas.graphics.quad_indices.clone(),
+            vec![set0],
+        )?;
+
+        cmd_buffer.run_ref(&pass)
+    }
+
     pub(super) fn render_text(
         &self,
         canvas: &CanvasData<D>,
@@ -183,6 +183,12 @@ impl<D, S> Canvas<D, S> {
         }
     }
 
+    fn drag_pos(&self, uv: Vec2, rect: &Rect) -> Vec2 {
+        let x = uv.x * self.canvas.width as f32;
+        let y = uv.y * self.canvas.height as f32;
+        Vec2::new((x - rect.x) / rect.w, (y - rect.y) / rect.h).clamp(Vec2::ZERO, Vec2::ONE)
+    }
+
     fn interactive_get_idx(&self, uv: Vec2) -> Option<usize> {
         let x = (uv.x * self.canvas.width as f32) as usize;
         let y = (uv.y * self.canvas.height as f32) as usize;
@@ -230,7 +236,15 @@ impl<D, S> InteractionHandler for Canvas<D, S> {
     fn on_left(&mut self, _app: &mut AppState, pointer: usize) {
         self.hover_controls[pointer] = None;
     }
-    fn on_hover(&mut self, _app: &mut AppState, hit: &PointerHit) -> Option<Haptics> {
+    fn on_hover(&mut self, app: &mut AppState, hit: &PointerHit) -> Option<Haptics> {
+        if let Some(idx) = self.pressed_controls[hit.pointer] {
+            let pos = self.drag_pos(hit.uv, &self.controls[idx].rect);
+            let c = &mut self.controls[idx];
+            if let Some(ref mut f) = c.on_drag {
+                f(c, &mut self.canvas.data, app, pos);
+            }
+        }
+
         let old = self.hover_controls[hit.pointer];
         if let Some(i) = self.interactive_get_idx(hit.uv) {
             self.hover_controls[hit.pointer] = Some(i);
@@ -255,15 +269,23 @@ impl<D, S> InteractionHandler for Canvas<D, S> {
         };
 
         if let Some(idx) = idx {
+            let pos = self.drag_pos(hit.uv, &self.controls[idx].rect);
             let c = &mut self.controls[idx];
             if pressed {
-                if let Some(ref mut f) = c.on_press {
+                if c.on_press.is_some() || c.on_drag.is_some() {
                     self.pressed_controls[hit.pointer] = Some(idx);
+                }
+                if let Some(ref mut f) = c.on_press {
                     f(c, &mut self.canvas.data, app, hit.mode);
                 }
-            } else if let Some(ref mut f) = c.on_release {
+                if let Some(ref mut f) = c.on_drag {
+                    f(c, &mut self.canvas.data, app, pos);
+                }
+            } else {
                 self.pressed_controls[hit.pointer] = None;
-                f(c, &mut self.canvas.data, app);
+                if let Some(ref mut f) = c.on_release {
+                    f(c, &mut self.canvas.data, app);
+                }
             }
         }
     }
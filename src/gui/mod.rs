@@ -4,6 +4,7 @@ use once_cell::sync::Lazy;
 pub mod canvas;
 pub mod font;
 pub mod modular;
+pub mod theme;
 
 pub type GuiColor = Vec4;
 pub(super) static FALLBACK_COLOR: Lazy<GuiColor> = Lazy::new(|| Vec4::new(1., 0., 1., 1.));
@@ -7,6 +7,7 @@ use std::{
 };
 
 use glam::{Quat, Vec4};
+use schemars::JsonSchema;
 use serde::Deserialize;
 
 use crate::{
@@ -16,10 +17,12 @@ use crate::{
         overlay::RelativeTo,
         task::{ColorChannel, SystemTask, TaskType},
     },
-    config::{save_layout, save_settings, AStrSetExt},
+    config::{save_layout, save_settings, AStrSetExt, GeneralConfig},
+    config_io,
+    gui::theme::theme_exists,
     hid::VirtualKey,
     overlays::{
-        toast::{Toast, ToastTopic},
+        toast::{is_valid_timeout, Toast, ToastTopic},
         watch::WATCH_NAME,
     },
     state::AppState,
@@ -30,13 +33,13 @@ use crate::overlays::wayvr::WayVRAction;
 
 use super::{ExecArgs, ModularControl, ModularData};
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Clone, JsonSchema)]
 pub enum PressRelease {
     Release,
     Press,
 }
 
-#[derive(Deserialize, Clone, Copy)]
+#[derive(Deserialize, Clone, Copy, JsonSchema)]
 pub enum ViewAngleKind {
     /// The cosine of the angle at which the watch becomes fully transparent
     MinOpacity,
@@ -44,14 +47,14 @@ pub enum ViewAngleKind {
     MaxOpacity,
 }
 
-#[derive(Deserialize, Clone, Copy)]
+#[derive(Deserialize, Clone, Copy, JsonSchema)]
 pub enum Axis {
     X,
     Y,
     Z,
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Clone, JsonSchema)]
 pub enum HighlightTest {
     AllowSliding,
     AutoRealign,
@@ -60,7 +63,7 @@ pub enum HighlightTest {
     RorateLock,
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Clone, JsonSchema)]
 pub enum SystemAction {
     ToggleAllowSliding,
     ToggleAutoRealign,
@@ -72,9 +75,26 @@ pub enum SystemAction {
     RecalculateExtent,
     PersistConfig,
     PersistLayout,
+    ClearNotifications,
+    /// Switch to a profile from the profiles directory, or back to no profile
+    SwitchProfile {
+        profile: Option<Arc<str>>,
+    },
+    /// Save the current overlay positions and open windows under a name
+    SaveLayout {
+        name: Arc<str>,
+    },
+    /// Move overlays back to a layout saved by SaveLayout
+    RestoreLayout {
+        name: Arc<str>,
+    },
+    /// Switch to a built-in or user-defined theme
+    SwitchTheme {
+        theme: Arc<str>,
+    },
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Clone, JsonSchema)]
 pub enum WatchAction {
     /// Hide the watch until Show/Hide binding is used
     Hide,
@@ -95,7 +115,7 @@ pub enum WatchAction {
     },
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Clone, JsonSchema)]
 pub enum OverlayAction {
     /// Reset the overlay to be in front of the HMD with its original scale
     Reset,
@@ -109,7 +129,7 @@ pub enum OverlayAction {
     Opacity { delta: f32 },
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Clone, JsonSchema)]
 pub enum WindowAction {
     /// Create a new mirror window, or show/hide an existing one
     ShowMirror,
@@ -119,7 +139,7 @@ pub enum WindowAction {
     Destroy,
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Clone, JsonSchema)]
 #[serde(tag = "type")]
 pub enum ButtonAction {
     Exec {
@@ -184,7 +204,7 @@ impl Default for PressData {
     }
 }
 
-#[derive(Deserialize, Default, Clone)]
+#[derive(Deserialize, Default, Clone, JsonSchema)]
 pub struct ButtonData {
     #[serde(skip)]
     pub(super) press: PressData,
@@ -326,6 +346,11 @@ fn modular_button_highlight(
     None
 }
 
+/// Runs an action outside of a button, e.g. when requested over IPC
+pub fn run_button_action(action: &ButtonAction, app: &mut AppState) {
+    handle_action(action, &mut PressData::default(), app);
+}
+
 fn handle_action(action: &ButtonAction, press: &mut PressData, app: &mut AppState) {
     match action {
         ButtonAction::Exec { command, toast } => run_exec(command, toast, press, app),
@@ -349,7 +374,7 @@ fn handle_action(action: &ButtonAction, press: &mut PressData, app: &mut AppStat
                 message.clone(),
                 body.clone().unwrap_or_else(|| "".into()),
             )
-            .with_timeout(seconds.unwrap_or(5.))
+            .with_timeout(seconds.filter(|s| is_valid_timeout(*s)).unwrap_or(5.))
             .submit(app);
         }
         ButtonAction::ColorAdjust { channel, delta } => {
@@ -469,7 +494,61 @@ fn run_system(action: &SystemAction, app: &mut AppState) {
                 log::error!("Failed to save layout: {:?}", e);
             }
         }
+        SystemAction::ClearNotifications => {
+            app.toast_history.clear();
+        }
+        SystemAction::SwitchProfile { profile } => switch_profile(profile.clone(), app),
+        SystemAction::SaveLayout { name } => {
+            app.tasks
+                .enqueue(TaskType::System(SystemTask::SaveLayout(name.clone())));
+        }
+        SystemAction::RestoreLayout { name } => {
+            app.tasks
+                .enqueue(TaskType::System(SystemTask::RestoreLayout(name.clone())));
+            audio_thump(app);
+        }
+        SystemAction::SwitchTheme { theme } => switch_theme(theme.clone(), app),
+    }
+}
+
+fn switch_profile(profile: Option<Arc<str>>, app: &mut AppState) {
+    if let Some(name) = profile.as_ref() {
+        if !config_io::list_profiles().contains(name) {
+            Toast::new(
+                ToastTopic::System,
+                format!("Profile {} not found", name).into(),
+                config_io::get_profiles_path().to_string_lossy().into(),
+            )
+            .submit(app);
+            return;
+        }
+    }
+
+    config_io::set_active_profile(profile.clone());
+    app.session.config = GeneralConfig::load_from_disk();
+    app.session.load_toast_rules();
+    app.tasks.enqueue(TaskType::System(SystemTask::ApplyConfig));
+
+    let title = match profile {
+        Some(name) => format!("Switched to profile {}", name),
+        None => "Switched to the default profile".into(),
+    };
+    Toast::new(ToastTopic::System, title.into(), "".into()).submit(app);
+}
+
+fn switch_theme(theme: Arc<str>, app: &mut AppState) {
+    if !theme_exists(&app.session.config, &theme) {
+        Toast::new(
+            ToastTopic::System,
+            format!("Theme {} not found", theme).into(),
+            "".into(),
+        )
+        .submit(app);
+        return;
     }
+
+    app.session.config.theme = theme;
+    app.tasks.enqueue(TaskType::System(SystemTask::ApplyTheme));
 }
 
 fn run_exec(args: &ExecArgs, toast: &Option<Arc<str>>, press: &mut PressData, app: &mut AppState) {
@@ -559,28 +638,14 @@ fn run_watch(data: &WatchAction, app: &mut AppState) {
             ));
             audio_thump(app);
         }
-        WatchAction::ViewAngle { kind, delta } => match kind {
-            ViewAngleKind::MinOpacity => {
-                let diff = (app.session.config.watch_view_angle_max
-                    - app.session.config.watch_view_angle_min)
-                    + delta;
-
-                app.session.config.watch_view_angle_min = (app.session.config.watch_view_angle_max
-                    - diff)
-                    .clamp(0.0, app.session.config.watch_view_angle_max - 0.05);
-            }
-            ViewAngleKind::MaxOpacity => {
-                let diff = app.session.config.watch_view_angle_max
-                    - app.session.config.watch_view_angle_min;
-
-                app.session.config.watch_view_angle_max =
-                    (app.session.config.watch_view_angle_max + delta).clamp(0.05, 1.0);
-
-                app.session.config.watch_view_angle_min = (app.session.config.watch_view_angle_max
-                    - diff)
-                    .clamp(0.0, app.session.config.watch_view_angle_max - 0.05);
-            }
-        },
+        WatchAction::ViewAngle { kind, delta } => {
+            let config = &mut app.session.config;
+            let value = match kind {
+                ViewAngleKind::MinOpacity => config.watch_view_angle_min - delta,
+                ViewAngleKind::MaxOpacity => config.watch_view_angle_max + delta,
+            };
+            set_view_angle(config, *kind, value);
+        }
         WatchAction::Rotation { axis, delta } => {
             let rot = match axis {
                 Axis::X => Quat::from_rotation_x(delta.to_radians()),
@@ -615,6 +680,21 @@ fn run_watch(data: &WatchAction, app: &mut AppState) {
     }
 }
 
+/// Moving the max keeps the distance to the min
+pub(super) fn set_view_angle(config: &mut GeneralConfig, kind: ViewAngleKind, value: f32) {
+    match kind {
+        ViewAngleKind::MinOpacity => {
+            config.watch_view_angle_min = value.clamp(0.0, config.watch_view_angle_max - 0.05);
+        }
+        ViewAngleKind::MaxOpacity => {
+            let diff = config.watch_view_angle_max - config.watch_view_angle_min;
+            config.watch_view_angle_max = value.clamp(0.05, 1.0);
+            config.watch_view_angle_min =
+                (config.watch_view_angle_max - diff).clamp(0.0, config.watch_view_angle_max - 0.05);
+        }
+    }
+}
+
 fn run_overlay(overlay: &OverlaySelector, action: &OverlayAction, app: &mut AppState) {
     match action {
         OverlayAction::Reset => {
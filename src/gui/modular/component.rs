@@ -0,0 +1,258 @@
+use std::{collections::HashMap, sync::Arc};
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{config_check, config_io};
+
+use super::{
+    layout::{Direction, GridLayout, LinearLayout},
+    ModularElement, ModularUiConfig,
+};
+
+// guards against components or includes that refer to themselves
+const MAX_DEPTH: usize = 8;
+
+/// A group of elements that can be placed many times with `type: Component`
+#[derive(Deserialize, JsonSchema, Clone)]
+pub struct ComponentDef {
+    /// Parameters and their default values, null if the parameter is required.
+    /// A string that is exactly `${name}` is replaced with the value,
+    /// `${name}` inside of a longer string with the value as text.
+    /// `$name` is left alone, that's a theme color.
+    #[serde(default)]
+    pub params: HashMap<Arc<str>, Value>,
+    /// Rects are relative to the position of the component
+    pub elements: Vec<Value>,
+}
+
+pub type Components = HashMap<Arc<str>, ComponentDef>;
+
+/// The part of a UI file that can be included by others
+#[derive(Deserialize)]
+struct ComponentLibrary {
+    #[serde(default)]
+    include: Vec<Arc<str>>,
+    #[serde(default)]
+    components: Components,
+}
+
+/// Returns the elements of the config with all components replaced by their elements
+/// and the children of containers moved into place.
+pub fn expand_elements(config: &ModularUiConfig) -> anyhow::Result<Vec<ModularElement>> {
+    let mut components = Components::new();
+    let mut included = vec![];
+    for file in config.include.iter() {
+        load_include(file, &mut components, &mut included, 0)?;
+    }
+    components.extend(
+        config
+            .components
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone())),
+    );
+
+    let mut elements = Vec::with_capacity(config.elements.len());
+    for elem in config.elements.iter() {
+        check_rect(elem)?;
+        expand_element(elem.clone(), &components, &mut elements, 0)?;
+    }
+    Ok(elements)
+}
+
+fn load_include(
+    file: &Arc<str>,
+    components: &mut Components,
+    included: &mut Vec<Arc<str>>,
+    depth: usize,
+) -> anyhow::Result<()> {
+    if included.contains(file) {
+        return Ok(());
+    }
+    if depth >= MAX_DEPTH {
+        anyhow::bail!("Includes are nested too deeply at {}", file);
+    }
+    included.push(file.clone());
+
+    let Some(yaml) = config_io::load(file) else {
+        anyhow::bail!("Could not read included file {}", file);
+    };
+    let library: ComponentLibrary = config_check::parse_yaml(file, &yaml)?;
+    for nested in library.include.iter() {
+        load_include(nested, components, included, depth + 1)?;
+    }
+    components.extend(library.components);
+    Ok(())
+}
+
+fn expand_element(
+    elem: ModularElement,
+    components: &Components,
+    out: &mut Vec<ModularElement>,
+    depth: usize,
+) -> anyhow::Result<()> {
+    let (component, pos, params) = match elem {
+        ModularElement::Component {
+            component,
+            pos,
+            params,
+        } => (component, pos, params),
+        ModularElement::Row {
+            rect,
+            padding,
+            gap,
+            align,
+            justify,
+            mut children,
+        } => {
+            LinearLayout {
+                direction: Direction::Horizontal,
+                padding,
+                gap,
+                align,
+                justify,
+            }
+            .apply(rect, &mut children);
+            return expand_children(children, components, out, depth);
+        }
+        ModularElement::Column {
+            rect,
+            padding,
+            gap,
+            align,
+            justify,
+            mut children,
+        } => {
+            LinearLayout {
+                direction: Direction::Vertical,
+                padding,
+                gap,
+                align,
+                justify,
+            }
+            .apply(rect, &mut children);
+            return expand_children(children, components, out, depth);
+        }
+        ModularElement::Grid {
+            rect,
+            columns,
+            padding,
+            gap,
+            align,
+            mut children,
+        } => {
+            GridLayout {
+                columns,
+                padding,
+                gap,
+                align,
+            }
+            .apply(rect, &mut children);
+            return expand_children(children, components, out, depth);
+        }
+        elem => {
+            out.push(elem);
+            return Ok(());
+        }
+    };
+
+    if depth >= MAX_DEPTH {
+        anyhow::bail!("Components are nested too deeply at {}", component);
+    }
+    let Some(def) = components.get(&component) else {
+        anyhow::bail!("Unknown component: {}", component);
+    };
+
+    if let Some(name) = params.keys().find(|k| !def.params.contains_key(*k)) {
+        anyhow::bail!("Component {} has no parameter {}", component, name);
+    }
+    let mut values = def.params.clone();
+    values.extend(params);
+    if let Some((name, _)) = values.iter().find(|(_, v)| v.is_null()) {
+        anyhow::bail!("Component {} needs parameter {}", component, name);
+    }
+
+    for raw in def.elements.iter() {
+        let mut raw = raw.clone();
+        substitute(&mut raw, &values);
+        let mut child: ModularElement = serde_json::from_value(raw)
+            .map_err(|e| anyhow::anyhow!("In component {}: {}", component, e))?;
+        check_rect(&child).map_err(|e| anyhow::anyhow!("In component {}: {}", component, e))?;
+        child.offset(pos[0], pos[1]);
+        expand_element(child, components, out, depth + 1)?;
+    }
+    Ok(())
+}
+
+fn expand_children(
+    children: Vec<ModularElement>,
+    components: &Components,
+    out: &mut Vec<ModularElement>,
+    depth: usize,
+) -> anyhow::Result<()> {
+    for child in children {
+        // has no size of its own that the layout could use
+        if let ModularElement::Component { component, .. } = &child {
+            anyhow::bail!(
+                "Component {} can not be placed inside of Row, Column or Grid",
+                component
+            );
+        }
+        expand_element(child, components, out, depth)?;
+    }
+    Ok(())
+}
+
+/// Only children of Row, Column and Grid may leave out their rect
+fn check_rect(elem: &ModularElement) -> anyhow::Result<()> {
+    if matches!(elem, ModularElement::Component { .. }) {
+        return Ok(());
+    }
+    let [x, y, w, h] = elem.rect();
+    if w <= 0. || h <= 0. {
+        anyhow::bail!(
+            "Element at [{}, {}] needs a rect with a width and height",
+            x,
+            y
+        );
+    }
+    Ok(())
+}
+
+fn substitute(value: &mut Value, params: &HashMap<Arc<str>, Value>) {
+    match value {
+        Value::String(s) => {
+            let whole = s.strip_prefix("${").and_then(|name| name.strip_suffix('}'));
+            if let Some(param) = whole.and_then(|name| params.get(name)) {
+                *value = param.clone();
+            } else if s.contains("${") {
+                *s = interpolate(s, params);
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(|v| substitute(v, params)),
+        Value::Object(map) => map.values_mut().for_each(|v| substitute(v, params)),
+        _ => {}
+    }
+}
+
+fn interpolate(text: &str, params: &HashMap<Arc<str>, Value>) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("${") {
+        let Some(len) = rest[start + 2..].find('}') else {
+            break;
+        };
+        let end = start + 2 + len;
+        out.push_str(&rest[..start]);
+        match params.get(&rest[start + 2..end]) {
+            Some(Value::String(s)) => out.push_str(s),
+            Some(v) => out.push_str(&v.to_string()),
+            // not a parameter, leave as is
+            None => out.push_str(&rest[start..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
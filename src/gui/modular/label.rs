@@ -9,20 +9,26 @@ use std::{
     time::Instant,
 };
 
-use crate::{gui::modular::FALLBACK_COLOR, state::AppState};
+use crate::{
+    gui::{modular::FALLBACK_COLOR, theme::Theme},
+    state::AppState,
+};
 
+use schemars::JsonSchema;
 use serde::Deserialize;
 
-use super::{color_parse_or_default, ExecArgs, GuiColor, ModularControl, ModularData};
+use super::{ExecArgs, GuiColor, ModularControl, ModularData};
+
+const MAX_NOTIFICATION_CHARS: usize = 80;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema, Clone)]
 #[serde(untagged)]
 pub enum TimezoneDef {
     Idx(usize),
     Str(Arc<str>),
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema, Clone)]
 #[serde(tag = "source")]
 pub enum LabelContent {
     Static {
@@ -50,6 +56,17 @@ pub enum LabelContent {
     },
     DragMultiplier,
     Ipd,
+    /// An entry of the notification history, 0 being the newest
+    Notification {
+        index: usize,
+        time_format: Option<Arc<str>>,
+    },
+    /// Placeholders for toast.yaml, filled in for each toast
+    ToastTitle,
+    ToastBody,
+    ToastTopic,
+    /// Drawn as a sprite, with the label's rect
+    ToastIcon,
 }
 
 pub enum LabelData {
@@ -78,9 +95,19 @@ pub enum LabelData {
         last_ipd: f32,
     },
     DragMultiplier,
+    Notification {
+        index: usize,
+        time_format: Arc<str>,
+        last_serial: Option<u64>,
+    },
 }
 
-pub fn modular_label_init(label: &mut ModularControl, content: &LabelContent, app: &AppState) {
+pub fn modular_label_init(
+    label: &mut ModularControl,
+    content: &LabelContent,
+    theme: &Theme,
+    app: &AppState,
+) {
     let state = match content {
         LabelContent::Battery {
             device,
@@ -91,8 +118,8 @@ pub fn modular_label_init(label: &mut ModularControl, content: &LabelContent, ap
             device: *device,
             low_threshold: *low_threshold,
             normal_color: label.fg_color,
-            low_color: color_parse_or_default(low_color),
-            charging_color: color_parse_or_default(charging_color),
+            low_color: theme.parse_or_default(low_color),
+            charging_color: theme.parse_or_default(charging_color),
         }),
         LabelContent::Clock { format, timezone } => {
             let tz_str = match timezone {
@@ -154,6 +181,16 @@ pub fn modular_label_init(label: &mut ModularControl, content: &LabelContent, ap
         }
         LabelContent::Ipd => Some(LabelData::Ipd { last_ipd: -1. }),
         LabelContent::DragMultiplier => Some(LabelData::DragMultiplier),
+        LabelContent::Notification { index, time_format } => Some(LabelData::Notification {
+            index: *index,
+            time_format: time_format.clone().unwrap_or_else(|| "%H:%M".into()),
+            last_serial: None,
+        }),
+        // only meaningful in toast.yaml, where they're replaced before we get here
+        LabelContent::ToastTitle
+        | LabelContent::ToastBody
+        | LabelContent::ToastTopic
+        | LabelContent::ToastIcon => None,
     };
 
     if let Some(state) = state {
@@ -296,5 +333,36 @@ pub(super) fn label_update(control: &mut ModularControl, _: &mut (), app: &mut A
         LabelData::DragMultiplier => {
             control.set_text(&format!("{:.1}", app.session.config.space_drag_multiplier));
         }
+        LabelData::Notification {
+            index,
+            time_format,
+            last_serial,
+        } => {
+            if *last_serial == Some(app.toast_history.serial) {
+                return;
+            }
+            *last_serial = Some(app.toast_history.serial);
+
+            let Some(entry) = app.toast_history.get(*index) else {
+                control.set_text("");
+                return;
+            };
+
+            let mut text = format!(
+                "{} [{}] {}",
+                entry.time.format(time_format),
+                entry.topic.label(),
+                entry.title
+            );
+            if !entry.body.is_empty() {
+                text.push_str(": ");
+                text.extend(entry.body.chars().map(|c| if c == '\n' { ' ' } else { c }));
+            }
+            if text.chars().count() > MAX_NOTIFICATION_CHARS {
+                text = text.chars().take(MAX_NOTIFICATION_CHARS - 3).collect();
+                text.push_str("...");
+            }
+            control.set_text(&text);
+        }
     }
 }
@@ -0,0 +1,200 @@
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use super::ModularElement;
+
+#[derive(Deserialize, JsonSchema, Clone, Copy, Default)]
+pub enum Align {
+    #[default]
+    Start,
+    Center,
+    End,
+}
+
+impl Align {
+    fn offset(self, free_space: f32) -> f32 {
+        match self {
+            Align::Start => 0.,
+            Align::Center => free_space * 0.5,
+            Align::End => free_space,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum Direction {
+    Horizontal,
+    Vertical,
+}
+
+pub struct LinearLayout {
+    pub direction: Direction,
+    pub padding: f32,
+    pub gap: f32,
+    pub align: Align,
+    pub justify: Align,
+}
+
+impl LinearLayout {
+    /// Sets the rects of the children, one after another along the direction.
+    /// A width (Row) or height (Column) of 0 shares the remaining space,
+    /// a size of 0 across the direction fills the container.
+    pub fn apply(&self, rect: [f32; 4], children: &mut [ModularElement]) {
+        let [x, y, w, h] = rect;
+        let (main_start, cross_start, main_len, cross_len) = match self.direction {
+            Direction::Horizontal => (x + self.padding, y + self.padding, w, h),
+            Direction::Vertical => (y + self.padding, x + self.padding, h, w),
+        };
+        let main_len = main_len - 2. * self.padding;
+        let cross_len = cross_len - 2. * self.padding;
+
+        let sizes: Vec<(f32, f32)> = children
+            .iter()
+            .map(|c| {
+                let [_, _, w, h] = c.rect();
+                match self.direction {
+                    Direction::Horizontal => (w, h),
+                    Direction::Vertical => (h, w),
+                }
+            })
+            .collect();
+
+        let fixed: f32 = sizes.iter().map(|(main, _)| main.max(0.)).sum();
+        let num_flexible = sizes.iter().filter(|(main, _)| *main <= 0.).count();
+        let gaps = self.gap * children.len().saturating_sub(1) as f32;
+        let free_space = (main_len - fixed - gaps).max(0.);
+
+        let (mut main, flexible_len) = if num_flexible > 0 {
+            (main_start, free_space / num_flexible as f32)
+        } else {
+            (main_start + self.justify.offset(free_space), 0.)
+        };
+
+        for (child, (main_size, cross_size)) in children.iter_mut().zip(sizes) {
+            let main_size = if main_size > 0. {
+                main_size
+            } else {
+                flexible_len
+            };
+            let cross_size = if cross_size > 0. {
+                cross_size.min(cross_len)
+            } else {
+                cross_len
+            };
+            let cross = cross_start + self.align.offset(cross_len - cross_size);
+
+            child.set_rect(match self.direction {
+                Direction::Horizontal => [main, cross, main_size, cross_size],
+                Direction::Vertical => [cross, main, cross_size, main_size],
+            });
+            main += main_size + self.gap;
+        }
+    }
+}
+
+pub struct GridLayout {
+    pub columns: usize,
+    pub padding: f32,
+    pub gap: f32,
+    pub align: Align,
+}
+
+impl GridLayout {
+    /// Sets the rects of the children to equally sized cells, filled row by row.
+    /// Children with a width or height of 0 fill their cell.
+    pub fn apply(&self, rect: [f32; 4], children: &mut [ModularElement]) {
+        let [x, y, w, h] = rect;
+        let columns = self.columns.max(1);
+        let rows = children.len().div_ceil(columns).max(1);
+
+        let cell_w = (w - 2. * self.padding - self.gap * (columns - 1) as f32) / columns as f32;
+        let cell_h = (h - 2. * self.padding - self.gap * (rows - 1) as f32) / rows as f32;
+
+        for (i, child) in children.iter_mut().enumerate() {
+            let cell_x = x + self.padding + (i % columns) as f32 * (cell_w + self.gap);
+            let cell_y = y + self.padding + (i / columns) as f32 * (cell_h + self.gap);
+
+            let [_, _, child_w, child_h] = child.rect();
+            let child_w = if child_w > 0. {
+                child_w.min(cell_w)
+            } else {
+                cell_w
+            };
+            let child_h = if child_h > 0. {
+                child_h.min(cell_h)
+            } else {
+                cell_h
+            };
+
+            child.set_rect([
+                cell_x + self.align.offset(cell_w - child_w),
+                cell_y + self.align.offset(cell_h - child_h),
+                child_w,
+                child_h,
+            ]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn panel(w: f32, h: f32) -> ModularElement {
+        ModularElement::Panel {
+            rect: [0., 0., w, h],
+            corner_radius: None,
+            bg_color: "".into(),
+        }
+    }
+
+    fn rects(children: &[ModularElement]) -> Vec<[f32; 4]> {
+        children.iter().map(|c| c.rect()).collect()
+    }
+
+    fn row(justify: Align) -> LinearLayout {
+        LinearLayout {
+            direction: Direction::Horizontal,
+            padding: 0.,
+            gap: 0.,
+            align: Align::Start,
+            justify,
+        }
+    }
+
+    #[test]
+    fn flexible_children_share_free_space() {
+        let mut children = vec![panel(40., 10.), panel(0., 0.), panel(0., 10.)];
+        row(Align::Start).apply([0., 0., 100., 20.], &mut children);
+        assert_eq!(
+            rects(&children),
+            vec![[0., 0., 40., 10.], [40., 0., 30., 20.], [70., 0., 30., 10.]]
+        );
+    }
+
+    #[test]
+    fn overflowing_row_starts_at_the_edge() {
+        let mut children = vec![panel(60., 20.), panel(60., 20.)];
+        row(Align::End).apply([0., 0., 100., 20.], &mut children);
+        assert_eq!(
+            rects(&children),
+            vec![[0., 0., 60., 20.], [60., 0., 60., 20.]]
+        );
+    }
+
+    #[test]
+    fn grid_with_zero_columns_is_one_column() {
+        let mut children = vec![panel(0., 0.), panel(0., 0.), panel(0., 0.)];
+        GridLayout {
+            columns: 0,
+            padding: 0.,
+            gap: 0.,
+            align: Align::Start,
+        }
+        .apply([0., 0., 50., 90.], &mut children);
+        assert_eq!(
+            rects(&children),
+            vec![[0., 0., 50., 30.], [0., 30., 50., 30.], [0., 60., 50., 30.]]
+        );
+    }
+}
@@ -1,9 +1,13 @@
 pub mod button;
+pub mod component;
 pub mod label;
+pub mod layout;
+pub mod slider;
 
-use std::{fs::File, sync::Arc};
+use std::{collections::HashMap, fs::File, sync::Arc};
 
 use glam::Vec4;
+use schemars::JsonSchema;
 use serde::Deserialize;
 use vulkano::{command_buffer::CommandBufferUsage, image::view::ImageView};
 
@@ -16,27 +20,39 @@ use crate::{
 use crate::overlays::wayvr::{WayVRAction, WayVRDisplayClickAction};
 
 use self::{
-    button::{modular_button_init, ButtonAction, ButtonData, OverlayAction},
+    button::{modular_button_init, ButtonAction, ButtonData, OverlayAction, SystemAction},
+    component::{expand_elements, Components},
     label::{modular_label_init, LabelContent, LabelData},
+    layout::Align,
+    slider::{modular_slider_init, SliderData, SliderDef},
 };
 
 use super::{
     canvas::{builder::CanvasBuilder, control::Control, Canvas},
-    color_parse, GuiColor, FALLBACK_COLOR,
+    theme::{Palette, Theme},
+    GuiColor, FALLBACK_COLOR,
 };
 
 type ModularControl = Control<(), ModularData>;
 type ExecArgs = Vec<Arc<str>>;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema, Clone)]
 pub struct ModularUiConfig {
     pub width: f32,
     pub size: [u32; 2],
     pub spawn_pos: Option<[f32; 3]>,
+    /// Overrides theme colors for this file only
+    #[serde(default)]
+    pub palette: Palette,
+    /// Files to take components from, relative to the config directory
+    #[serde(default)]
+    pub include: Vec<Arc<str>>,
+    #[serde(default)]
+    pub components: Components,
     pub elements: Vec<ModularElement>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema, Clone)]
 pub struct OverlayListTemplate {
     click_down: Option<OverlayAction>,
     click_up: Option<OverlayAction>,
@@ -51,16 +67,20 @@ pub struct OverlayListTemplate {
     scroll_up: Option<OverlayAction>,
 }
 
+/// Inside of Row, Column and Grid, only the size of the rect is used and can be left out.
+/// Elsewhere, the rect needs a width and height.
 #[allow(dead_code)]
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema, Clone)]
 #[serde(tag = "type")]
 pub enum ModularElement {
     Panel {
+        #[serde(default)]
         rect: [f32; 4],
         corner_radius: Option<f32>,
         bg_color: Arc<str>,
     },
     Label {
+        #[serde(default)]
         rect: [f32; 4],
         corner_radius: Option<f32>,
         font_size: isize,
@@ -69,6 +89,7 @@ pub enum ModularElement {
         data: LabelContent,
     },
     CenteredLabel {
+        #[serde(default)]
         rect: [f32; 4],
         corner_radius: Option<f32>,
         font_size: isize,
@@ -77,11 +98,13 @@ pub enum ModularElement {
         data: LabelContent,
     },
     Sprite {
+        #[serde(default)]
         rect: [f32; 4],
         sprite: Arc<str>,
         sprite_st: Option<[f32; 4]>,
     },
     Button {
+        #[serde(default)]
         rect: [f32; 4],
         corner_radius: Option<f32>,
         font_size: isize,
@@ -91,8 +114,22 @@ pub enum ModularElement {
         #[serde(flatten)]
         data: Box<ButtonData>,
     },
+    /// Drag or scroll to set a numeric setting. The text is shown in front of the value.
+    Slider {
+        #[serde(default)]
+        rect: [f32; 4],
+        corner_radius: Option<f32>,
+        font_size: isize,
+        fg_color: Arc<str>,
+        bg_color: Arc<str>,
+        fill_color: Arc<str>,
+        text: Option<Arc<str>>,
+        #[serde(flatten)]
+        data: SliderDef,
+    },
     /// Convenience type to save you from having to create a bunch of labels
     BatteryList {
+        #[serde(default)]
         rect: [f32; 4],
         corner_radius: Option<f32>,
         font_size: isize,
@@ -104,6 +141,7 @@ pub enum ModularElement {
         layout: ListLayout,
     },
     OverlayList {
+        #[serde(default)]
         rect: [f32; 4],
         corner_radius: Option<f32>,
         font_size: isize,
@@ -113,8 +151,20 @@ pub enum ModularElement {
         #[serde(flatten)]
         template: Box<OverlayListTemplate>,
     },
+    /// Recent notifications, newest on top, with a clear button below
+    NotificationList {
+        #[serde(default)]
+        rect: [f32; 4],
+        corner_radius: Option<f32>,
+        font_size: isize,
+        fg_color: Arc<str>,
+        bg_color: Arc<str>,
+        num_entries: usize,
+        time_format: Option<Arc<str>>,
+    },
     // Ignored if "wayvr" feature is not enabled
     WayVRLauncher {
+        #[serde(default)]
         rect: [f32; 4],
         corner_radius: Option<f32>,
         font_size: isize,
@@ -124,12 +174,113 @@ pub enum ModularElement {
     },
     // Ignored if "wayvr" feature is not enabled
     WayVRDisplayList {
+        #[serde(default)]
         rect: [f32; 4],
         corner_radius: Option<f32>,
         font_size: isize,
         fg_color: Arc<str>,
         bg_color: Arc<str>,
     },
+    /// Places children left to right. Children with a width of 0 share the remaining space,
+    /// children with a height of 0 fill the row.
+    Row {
+        #[serde(default)]
+        rect: [f32; 4],
+        #[serde(default)]
+        padding: f32,
+        #[serde(default)]
+        gap: f32,
+        /// Vertical position of children that are less tall than the row
+        #[serde(default)]
+        align: Align,
+        /// Horizontal position of the children if none of them has a width of 0
+        #[serde(default)]
+        justify: Align,
+        children: Vec<ModularElement>,
+    },
+    /// Same as Row, top to bottom
+    Column {
+        #[serde(default)]
+        rect: [f32; 4],
+        #[serde(default)]
+        padding: f32,
+        #[serde(default)]
+        gap: f32,
+        #[serde(default)]
+        align: Align,
+        #[serde(default)]
+        justify: Align,
+        children: Vec<ModularElement>,
+    },
+    /// Places children in equally sized cells, row by row
+    Grid {
+        #[serde(default)]
+        rect: [f32; 4],
+        columns: usize,
+        #[serde(default)]
+        padding: f32,
+        #[serde(default)]
+        gap: f32,
+        #[serde(default)]
+        align: Align,
+        children: Vec<ModularElement>,
+    },
+    /// Places the elements of a component defined under `components`, moved by pos.
+    /// Can not be a child of Row, Column or Grid.
+    Component {
+        component: Arc<str>,
+        pos: [f32; 2],
+        #[serde(default)]
+        params: HashMap<Arc<str>, serde_json::Value>,
+    },
+}
+
+impl ModularElement {
+    fn rect(&self) -> [f32; 4] {
+        match self {
+            ModularElement::Panel { rect, .. }
+            | ModularElement::Label { rect, .. }
+            | ModularElement::CenteredLabel { rect, .. }
+            | ModularElement::Sprite { rect, .. }
+            | ModularElement::Button { rect, .. }
+            | ModularElement::Slider { rect, .. }
+            | ModularElement::BatteryList { rect, .. }
+            | ModularElement::OverlayList { rect, .. }
+            | ModularElement::NotificationList { rect, .. }
+            | ModularElement::WayVRLauncher { rect, .. }
+            | ModularElement::WayVRDisplayList { rect, .. }
+            | ModularElement::Row { rect, .. }
+            | ModularElement::Column { rect, .. }
+            | ModularElement::Grid { rect, .. } => *rect,
+            // sized by its elements
+            ModularElement::Component { pos, .. } => [pos[0], pos[1], 0., 0.],
+        }
+    }
+
+    fn set_rect(&mut self, new_rect: [f32; 4]) {
+        match self {
+            ModularElement::Panel { rect, .. }
+            | ModularElement::Label { rect, .. }
+            | ModularElement::CenteredLabel { rect, .. }
+            | ModularElement::Sprite { rect, .. }
+            | ModularElement::Button { rect, .. }
+            | ModularElement::Slider { rect, .. }
+            | ModularElement::BatteryList { rect, .. }
+            | ModularElement::OverlayList { rect, .. }
+            | ModularElement::NotificationList { rect, .. }
+            | ModularElement::WayVRLauncher { rect, .. }
+            | ModularElement::WayVRDisplayList { rect, .. }
+            | ModularElement::Row { rect, .. }
+            | ModularElement::Column { rect, .. }
+            | ModularElement::Grid { rect, .. } => *rect = new_rect,
+            ModularElement::Component { pos, .. } => *pos = [new_rect[0], new_rect[1]],
+        }
+    }
+
+    fn offset(&mut self, x: f32, y: f32) {
+        let [rect_x, rect_y, w, h] = self.rect();
+        self.set_rect([rect_x + x, rect_y + y, w, h]);
+    }
 }
 
 #[derive(Deserialize, Clone)]
@@ -138,7 +289,7 @@ pub enum ButtonFunc {
     SwitchWatchHand,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema, Clone)]
 pub enum ListLayout {
     Horizontal,
     Vertical,
@@ -147,20 +298,22 @@ pub enum ListLayout {
 pub enum ModularData {
     Label(Box<LabelData>),
     Button(Box<ButtonData>),
+    Slider(Box<SliderData>),
 }
 
 pub fn modular_canvas(
-    size: &[u32; 2],
-    elements: &[ModularElement],
+    config: &ModularUiConfig,
     state: &mut AppState,
 ) -> anyhow::Result<Canvas<(), ModularData>> {
     let mut canvas = CanvasBuilder::new(
-        size[0] as _,
-        size[1] as _,
+        config.size[0] as _,
+        config.size[1] as _,
         state.graphics.clone(),
         state.graphics.native_format,
         (),
     )?;
+    let theme = Theme::from_config(&state.session.config).with_overrides(&config.palette);
+    let elements = expand_elements(config)?;
     let empty_str: Arc<str> = Arc::from("");
     for elem in elements.iter() {
         match elem {
@@ -169,7 +322,7 @@ pub fn modular_canvas(
                 corner_radius,
                 bg_color,
             } => {
-                canvas.bg_color = color_parse(bg_color).unwrap_or(*FALLBACK_COLOR);
+                canvas.bg_color = theme.parse(bg_color).unwrap_or(*FALLBACK_COLOR);
                 canvas.panel(*x, *y, *w, *h, corner_radius.unwrap_or_default());
             }
             ModularElement::Label {
@@ -180,7 +333,7 @@ pub fn modular_canvas(
                 data,
             } => {
                 canvas.font_size = *font_size;
-                canvas.fg_color = color_parse(fg_color).unwrap_or(*FALLBACK_COLOR);
+                canvas.fg_color = theme.parse(fg_color).unwrap_or(*FALLBACK_COLOR);
                 let label = canvas.label(
                     *x,
                     *y,
@@ -189,7 +342,7 @@ pub fn modular_canvas(
                     corner_radius.unwrap_or_default(),
                     empty_str.clone(),
                 );
-                modular_label_init(label, data, state);
+                modular_label_init(label, data, &theme, state);
             }
             ModularElement::CenteredLabel {
                 rect: [x, y, w, h],
@@ -199,7 +352,7 @@ pub fn modular_canvas(
                 data,
             } => {
                 canvas.font_size = *font_size;
-                canvas.fg_color = color_parse(fg_color).unwrap_or(*FALLBACK_COLOR);
+                canvas.fg_color = theme.parse(fg_color).unwrap_or(*FALLBACK_COLOR);
                 let label = canvas.label_centered(
                     *x,
                     *y,
@@ -208,7 +361,7 @@ pub fn modular_canvas(
                     corner_radius.unwrap_or_default(),
                     empty_str.clone(),
                 );
-                modular_label_init(label, data, state);
+                modular_label_init(label, data, &theme, state);
             }
             ModularElement::Sprite {
                 rect: [x, y, w, h],
@@ -238,8 +391,8 @@ pub fn modular_canvas(
                 text,
                 data,
             } => {
-                canvas.bg_color = color_parse(bg_color).unwrap_or(*FALLBACK_COLOR);
-                canvas.fg_color = color_parse(fg_color).unwrap_or(*FALLBACK_COLOR);
+                canvas.bg_color = theme.parse(bg_color).unwrap_or(*FALLBACK_COLOR);
+                canvas.fg_color = theme.parse(fg_color).unwrap_or(*FALLBACK_COLOR);
                 canvas.font_size = *font_size;
                 let button = canvas.button(
                     *x,
@@ -251,6 +404,29 @@ pub fn modular_canvas(
                 );
                 modular_button_init(button, data);
             }
+            ModularElement::Slider {
+                rect: [x, y, w, h],
+                corner_radius,
+                font_size,
+                fg_color,
+                bg_color,
+                fill_color,
+                text,
+                data,
+            } => {
+                canvas.bg_color = theme.parse(bg_color).unwrap_or(*FALLBACK_COLOR);
+                canvas.fg_color = theme.parse(fg_color).unwrap_or(*FALLBACK_COLOR);
+                canvas.font_size = *font_size;
+                let slider = canvas.slider(
+                    *x,
+                    *y,
+                    *w,
+                    *h,
+                    corner_radius.unwrap_or_default(),
+                    theme.parse(fill_color).unwrap_or(*FALLBACK_COLOR),
+                );
+                modular_slider_init(slider, data, text.clone(), state);
+            }
             ModularElement::BatteryList {
                 rect: [x, y, w, h],
                 corner_radius,
@@ -271,7 +447,7 @@ pub fn modular_canvas(
                     ListLayout::Vertical => (*w, *h / num_buttons),
                 };
 
-                let fg_color = color_parse(fg_color).unwrap_or(*FALLBACK_COLOR);
+                let fg_color = theme.parse(fg_color).unwrap_or(*FALLBACK_COLOR);
                 canvas.font_size = *font_size;
                 canvas.fg_color = fg_color;
 
@@ -292,6 +468,7 @@ pub fn modular_canvas(
                             low_color: fg_color_low.clone(),
                             charging_color: fg_color_charging.clone(),
                         },
+                        &theme,
                         state,
                     );
 
@@ -322,8 +499,8 @@ pub fn modular_canvas(
                     ListLayout::Vertical => (*w, *h / num_buttons),
                 };
 
-                canvas.bg_color = color_parse(bg_color).unwrap_or(*FALLBACK_COLOR);
-                canvas.fg_color = color_parse(fg_color).unwrap_or(*FALLBACK_COLOR);
+                canvas.bg_color = theme.parse(bg_color).unwrap_or(*FALLBACK_COLOR);
+                canvas.fg_color = theme.parse(fg_color).unwrap_or(*FALLBACK_COLOR);
                 canvas.font_size = *font_size;
 
                 for screen in state.screens.iter() {
@@ -419,6 +596,58 @@ pub fn modular_canvas(
                     };
                 }
             }
+            ModularElement::NotificationList {
+                rect: [x, y, w, h],
+                corner_radius,
+                font_size,
+                fg_color,
+                bg_color,
+                num_entries,
+                time_format,
+            } => {
+                // one extra row for the clear button
+                let row_h = *h / (*num_entries + 1) as f32;
+
+                canvas.fg_color = theme.parse(fg_color).unwrap_or(*FALLBACK_COLOR);
+                canvas.bg_color = theme.parse(bg_color).unwrap_or(*FALLBACK_COLOR);
+                canvas.font_size = *font_size;
+
+                for i in 0..*num_entries {
+                    let label = canvas.label(
+                        *x + 2.,
+                        *y + row_h * i as f32 + 2.,
+                        *w - 4.,
+                        row_h - 4.,
+                        corner_radius.unwrap_or_default(),
+                        empty_str.clone(),
+                    );
+                    modular_label_init(
+                        label,
+                        &LabelContent::Notification {
+                            index: i,
+                            time_format: time_format.clone(),
+                        },
+                        &theme,
+                        state,
+                    );
+                }
+
+                let button = canvas.button(
+                    *x + 2.,
+                    *y + row_h * *num_entries as f32 + 2.,
+                    *w - 4.,
+                    row_h - 4.,
+                    corner_radius.unwrap_or_default(),
+                    "Clear".into(),
+                );
+                let data = ButtonData {
+                    click_up: Some(vec![ButtonAction::System {
+                        action: SystemAction::ClearNotifications,
+                    }]),
+                    ..Default::default()
+                };
+                modular_button_init(button, &data);
+            }
             #[allow(unused_variables)] // needed in case if wayvr feature is not enabled
             ModularElement::WayVRLauncher {
                 rect: [x, y, w, h],
@@ -438,8 +667,8 @@ pub fn modular_canvas(
                             let button_w: f32 = *w / catalog.apps.len() as f32;
                             let button_h: f32 = *h;
 
-                            canvas.bg_color = color_parse(bg_color).unwrap_or(*FALLBACK_COLOR);
-                            canvas.fg_color = color_parse(fg_color).unwrap_or(*FALLBACK_COLOR);
+                            canvas.bg_color = theme.parse(bg_color).unwrap_or(*FALLBACK_COLOR);
+                            canvas.fg_color = theme.parse(fg_color).unwrap_or(*FALLBACK_COLOR);
                             canvas.font_size = *font_size;
 
                             let button = canvas.button(
@@ -488,8 +717,8 @@ pub fn modular_canvas(
                         let button_w: f32 = (*w / displays.len() as f32).min(80.0);
                         let button_h: f32 = *h;
 
-                        canvas.bg_color = color_parse(bg_color).unwrap_or(*FALLBACK_COLOR);
-                        canvas.fg_color = color_parse(fg_color).unwrap_or(*FALLBACK_COLOR);
+                        canvas.bg_color = theme.parse(bg_color).unwrap_or(*FALLBACK_COLOR);
+                        canvas.fg_color = theme.parse(fg_color).unwrap_or(*FALLBACK_COLOR);
                         canvas.font_size = *font_size;
 
                         let button = canvas.button(
@@ -524,18 +753,16 @@ pub fn modular_canvas(
                     log::error!("WayVR feature is not enabled, ignoring")
                 }
             }
+            // already replaced by expand_elements
+            ModularElement::Row { .. }
+            | ModularElement::Column { .. }
+            | ModularElement::Grid { .. }
+            | ModularElement::Component { .. } => {}
         }
     }
     Ok(canvas.build())
 }
 
-pub fn color_parse_or_default(color: &str) -> GuiColor {
-    color_parse(color).unwrap_or_else(|e| {
-        log::error!("Failed to parse color '{}': {}", color, e);
-        *FALLBACK_COLOR
-    })
-}
-
 fn sprite_from_path(path: Arc<str>, app: &mut AppState) -> anyhow::Result<Arc<ImageView>> {
     if let Some(view) = app.sprites.arc_get(&path) {
         return Ok(view.clone());
@@ -0,0 +1,230 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use glam::Vec2;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::{
+    backend::{
+        common::OverlaySelector,
+        task::{ColorChannel, SystemTask, TaskType},
+    },
+    state::AppState,
+};
+
+use super::{
+    button::{set_view_angle, ViewAngleKind},
+    ModularControl, ModularData,
+};
+
+// rebuild at most this often while the slider is moving
+const KEYBOARD_REBUILD_DELAY: Duration = Duration::from_millis(500);
+
+// fully transparent overlays can not be found again
+const MIN_OPACITY: f32 = 0.1;
+
+#[derive(Deserialize, JsonSchema, Clone)]
+#[serde(tag = "source")]
+pub enum SliderSource {
+    DragMultiplier,
+    /// The fade behavior of the watch
+    ViewAngle {
+        kind: ViewAngleKind,
+    },
+    /// Applied to the keyboard shortly after the slider moves
+    KeyboardScale,
+    /// Can not be read back, starts at `initial`
+    ColorGain {
+        channel: ColorChannel,
+    },
+    /// Can not be read back, starts at `initial`. Limited to 0.1..=1.0
+    Opacity {
+        target: OverlaySelector,
+    },
+}
+
+#[derive(Deserialize, JsonSchema, Clone)]
+pub struct SliderDef {
+    pub min: f32,
+    pub max: f32,
+    /// Values snap to multiples of step, 0 to not snap
+    #[serde(default)]
+    pub step: f32,
+    /// Starting value for sources that can not be read back, defaults to max
+    pub initial: Option<f32>,
+    #[serde(flatten)]
+    pub source: SliderSource,
+}
+
+pub struct SliderData {
+    source: SliderSource,
+    min: f32,
+    max: f32,
+    step: f32,
+    text: Arc<str>,
+    decimals: usize,
+    value: f32,
+    rebuild_at: Option<Instant>,
+}
+
+impl SliderData {
+    fn snap(&self, value: f32) -> f32 {
+        let value = if self.step > 0. {
+            self.min + ((value - self.min) / self.step).round() * self.step
+        } else {
+            value
+        };
+        value.clamp(self.min, self.max)
+    }
+
+    fn read(&self, app: &AppState) -> Option<f32> {
+        let config = &app.session.config;
+        match &self.source {
+            SliderSource::DragMultiplier => Some(config.space_drag_multiplier),
+            SliderSource::ViewAngle { kind } => Some(match kind {
+                ViewAngleKind::MinOpacity => config.watch_view_angle_min,
+                ViewAngleKind::MaxOpacity => config.watch_view_angle_max,
+            }),
+            SliderSource::KeyboardScale => Some(config.keyboard_scale),
+            SliderSource::ColorGain { .. } | SliderSource::Opacity { .. } => None,
+        }
+    }
+
+    fn write(&mut self, value: f32, app: &mut AppState) {
+        let config = &mut app.session.config;
+        match &self.source {
+            SliderSource::DragMultiplier => {
+                config.space_drag_multiplier = value;
+            }
+            SliderSource::ViewAngle { kind } => {
+                set_view_angle(config, *kind, value);
+            }
+            SliderSource::KeyboardScale => {
+                config.keyboard_scale = value;
+                // the rebuild picks up any changes made until it runs
+                let now = Instant::now();
+                if !matches!(self.rebuild_at, Some(at) if at > now) {
+                    let at = now + KEYBOARD_REBUILD_DELAY;
+                    self.rebuild_at = Some(at);
+                    app.tasks
+                        .enqueue_at(TaskType::System(SystemTask::RebuildKeyboard), at);
+                }
+            }
+            SliderSource::ColorGain { channel } => {
+                app.tasks
+                    .enqueue(TaskType::System(SystemTask::SetColorGain(*channel, value)));
+            }
+            SliderSource::Opacity { target } => {
+                app.tasks.enqueue(TaskType::Overlay(
+                    target.clone(),
+                    Box::new(move |_, o| {
+                        o.alpha = value;
+                        o.dirty = true;
+                    }),
+                ));
+            }
+        }
+        self.value = self.read(app).unwrap_or(value);
+    }
+
+    fn set(&mut self, value: f32, app: &mut AppState) {
+        let value = self.snap(value);
+        if value != self.value {
+            self.write(value, app);
+        }
+    }
+}
+
+pub fn modular_slider_init(
+    slider: &mut ModularControl,
+    def: &SliderDef,
+    text: Option<Arc<str>>,
+    app: &AppState,
+) {
+    // as many digits as the step has, so that 0.25 shows as 0.25
+    let decimals = if def.step > 0. {
+        let step = def.step.to_string();
+        step.split_once('.')
+            .map_or(0, |(_, frac)| frac.len().min(4))
+    } else {
+        2
+    };
+
+    let (min, max) = match def.source {
+        SliderSource::Opacity { .. } => (
+            def.min.clamp(MIN_OPACITY, 1.0),
+            def.max.clamp(MIN_OPACITY, 1.0),
+        ),
+        _ => (def.min, def.max),
+    };
+
+    let mut data = SliderData {
+        source: def.source.clone(),
+        min,
+        max: max.max(min),
+        step: def.step,
+        text: text.unwrap_or_else(|| "".into()),
+        decimals,
+        value: max,
+        rebuild_at: None,
+    };
+    data.value = data.snap(data.read(app).or(def.initial).unwrap_or(max));
+
+    slider.state = Some(ModularData::Slider(Box::new(data)));
+    slider.on_update = Some(modular_slider_update);
+    slider.on_drag = Some(modular_slider_drag);
+    slider.on_scroll = Some(modular_slider_scroll);
+}
+
+fn modular_slider_update(slider: &mut ModularControl, _: &mut (), app: &mut AppState) {
+    // want panic
+    let ModularData::Slider(data) = slider.state.as_mut().unwrap() else {
+        panic!("modular_slider_update: slider state is not Slider");
+    };
+
+    if let Some(value) = data.read(app) {
+        data.value = value;
+    }
+
+    let fill = if data.max > data.min {
+        (data.value - data.min) / (data.max - data.min)
+    } else {
+        1.
+    };
+    let text = if data.text.is_empty() {
+        format!("{:.*}", data.decimals, data.value)
+    } else {
+        format!("{} {:.*}", data.text, data.decimals, data.value)
+    };
+
+    slider.set_fill(fill);
+    slider.set_text(&text);
+}
+
+fn modular_slider_drag(slider: &mut ModularControl, _: &mut (), app: &mut AppState, pos: Vec2) {
+    // want panic
+    let ModularData::Slider(data) = slider.state.as_mut().unwrap() else {
+        panic!("modular_slider_drag: slider state is not Slider");
+    };
+
+    let value = data.min + pos.x * (data.max - data.min);
+    data.set(value, app);
+}
+
+fn modular_slider_scroll(slider: &mut ModularControl, _: &mut (), app: &mut AppState, delta: f32) {
+    // want panic
+    let ModularData::Slider(data) = slider.state.as_mut().unwrap() else {
+        panic!("modular_slider_scroll: slider state is not Slider");
+    };
+
+    let step = if data.step > 0. {
+        data.step
+    } else {
+        (data.max - data.min) * 0.01
+    };
+    let value = data.value + step * delta.signum();
+    data.set(value, app);
+}
@@ -0,0 +1,117 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::config::GeneralConfig;
+
+use super::{color_parse, GuiColor, FALLBACK_COLOR};
+
+/// Named colors, referred to as `$name` wherever a color is accepted
+pub type Palette = HashMap<Arc<str>, Arc<str>>;
+
+pub const DEFAULT_THEME: &str = "dark";
+
+// Catppuccin Macchiato
+const DARK: &[(&str, &str)] = &[
+    ("bg", "#24273a"),
+    ("bg_alt", "#1e2030"),
+    ("bg_dark", "#181926"),
+    ("surface_dim", "#363a4f"),
+    ("surface", "#494d64"),
+    ("surface_bright", "#5b6078"),
+    ("muted", "#6e738d"),
+    ("fg", "#cad3f5"),
+    ("fg_dim", "#b8c0e0"),
+    ("accent", "#eed49f"),
+    ("danger", "#ed8796"),
+    ("success", "#a6da95"),
+    ("info", "#8bd5ca"),
+    ("highlight", "#c6a0f6"),
+    ("secondary", "#b7bdf8"),
+];
+
+// Catppuccin Latte
+const LIGHT: &[(&str, &str)] = &[
+    ("bg", "#eff1f5"),
+    ("bg_alt", "#e6e9ef"),
+    ("bg_dark", "#dce0e8"),
+    ("surface_dim", "#ccd0da"),
+    ("surface", "#bcc0cc"),
+    ("surface_bright", "#acb0be"),
+    ("muted", "#9ca0b0"),
+    ("fg", "#4c4f69"),
+    ("fg_dim", "#5c5f77"),
+    ("accent", "#df8e1d"),
+    ("danger", "#d20f39"),
+    ("success", "#40a02b"),
+    ("info", "#179299"),
+    ("highlight", "#8839ef"),
+    ("secondary", "#7287fd"),
+];
+
+fn builtin(name: &str) -> Option<&'static [(&'static str, &'static str)]> {
+    match name {
+        "dark" => Some(DARK),
+        "light" => Some(LIGHT),
+        _ => None,
+    }
+}
+
+/// True if the name is a built-in theme or one defined under `themes`
+pub fn theme_exists(config: &GeneralConfig, name: &str) -> bool {
+    builtin(name).is_some() || config.themes.contains_key(name)
+}
+
+/// Resolves colors against the active theme.
+pub struct Theme {
+    colors: Palette,
+}
+
+impl Theme {
+    /// The active theme with the global palette applied on top.
+    /// User themes only need to list the colors they change from the dark theme.
+    pub fn from_config(config: &GeneralConfig) -> Self {
+        let mut colors: Palette = DARK
+            .iter()
+            .map(|(name, color)| (Arc::from(*name), Arc::from(*color)))
+            .collect();
+
+        if let Some(user) = config.themes.get(&config.theme) {
+            colors.extend(user.iter().map(|(k, v)| (k.clone(), v.clone())));
+        } else if let Some(builtin) = builtin(&config.theme) {
+            colors.extend(
+                builtin
+                    .iter()
+                    .map(|(name, color)| (Arc::from(*name), Arc::from(*color))),
+            );
+        } else {
+            log::warn!("Unknown theme '{}', using {}", config.theme, DEFAULT_THEME);
+        }
+
+        colors.extend(config.palette.iter().map(|(k, v)| (k.clone(), v.clone())));
+        Self { colors }
+    }
+
+    /// Applies the palette of a single UI file.
+    pub fn with_overrides(mut self, palette: &Palette) -> Self {
+        self.colors
+            .extend(palette.iter().map(|(k, v)| (k.clone(), v.clone())));
+        self
+    }
+
+    /// Parses a HTML hex string, or looks up a `$name` in the palette
+    pub fn parse(&self, color: &str) -> anyhow::Result<GuiColor> {
+        let Some(name) = color.strip_prefix('$') else {
+            return color_parse(color);
+        };
+        match self.colors.get(name) {
+            Some(value) => color_parse(value),
+            None => anyhow::bail!("Unknown theme color: '{}'", color),
+        }
+    }
+
+    pub fn parse_or_default(&self, color: &str) -> GuiColor {
+        self.parse(color).unwrap_or_else(|e| {
+            log::error!("Failed to parse color '{}': {}", color, e);
+            *FALLBACK_COLOR
+        })
+    }
+}
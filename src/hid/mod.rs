@@ -7,6 +7,7 @@ use input_linux::{
 };
 use libc::{input_event, timeval};
 use once_cell::sync::Lazy;
+use schemars::JsonSchema;
 use serde::Deserialize;
 use std::mem::transmute;
 use std::{fs::File, sync::atomic::AtomicBool};
@@ -317,7 +318,9 @@ pub const META: KeyModifier = 0x80;
 
 #[allow(non_camel_case_types)]
 #[repr(u16)]
-#[derive(Debug, Deserialize, PartialEq, Clone, Copy, IntegerId, EnumString, EnumIter)]
+#[derive(
+    Debug, Deserialize, PartialEq, Clone, Copy, IntegerId, EnumString, EnumIter, JsonSchema,
+)]
 pub enum VirtualKey {
     Escape = 9,
     N1, // number row
@@ -536,6 +539,7 @@ pub fn get_key_type(key: VirtualKey) -> KeyType {
     }
 }
 
+#[derive(Clone)]
 pub struct XkbKeymap {
     pub keymap: xkb::Keymap,
 }
@@ -1,7 +1,10 @@
 #[allow(dead_code)]
 mod backend;
 mod config;
+mod config_check;
 mod config_io;
+mod config_schema;
+mod ctl;
 mod graphics;
 mod gui;
 mod hid;
@@ -20,7 +23,10 @@ use std::{
     },
 };
 
-use clap::Parser;
+use backend::input_record::InputSession;
+use clap::{Parser, Subcommand};
+use config_schema::SchemaKind;
+use ctl::CtlCommand;
 use sysinfo::Pid;
 
 /// The lightweight desktop overlay for OpenVR and OpenXR
@@ -53,6 +59,18 @@ struct Args {
     #[arg(long)]
     multi: bool,
 
+    /// Load a profile from the profiles directory on top of conf.d
+    #[arg(long, value_name = "NAME")]
+    profile: Option<String>,
+
+    /// Check the config files for problems and exit
+    #[arg(long)]
+    check_config: bool,
+
+    /// Print the JSON Schema of a config format and exit
+    #[arg(long, value_name = "KIND")]
+    dump_schema: Option<SchemaKind>,
+
     /// Path to write logs to
     #[arg(short, long, value_name = "FILE_PATH")]
     log_to: Option<String>,
@@ -61,6 +79,32 @@ struct Args {
     /// Show a desktop window of a UI panel for development
     #[arg(short, long, value_name = "UI_NAME")]
     uidev: Option<String>,
+
+    #[cfg(feature = "headless")]
+    /// Run without a VR runtime, simulating input from a script.
+    /// Does not touch the pid file, control socket, D-Bus or config watcher of a running instance
+    #[arg(long, value_name = "SCRIPT_PATH")]
+    headless: Option<PathBuf>,
+
+    /// Record controller and headset input to a file
+    #[arg(long, value_name = "FILE_PATH", conflicts_with = "replay")]
+    record: Option<PathBuf>,
+
+    /// Replay input from a recording instead of live controller input
+    #[arg(long, value_name = "FILE_PATH")]
+    replay: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Control an already running instance
+    Ctl {
+        #[command(subcommand)]
+        command: CtlCommand,
+    },
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -70,9 +114,40 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         Args::default()
     };
 
-    if !args.multi && !ensure_single_instance(args.replace) {
+    if let Some(Command::Ctl { command }) = args.command.as_ref() {
+        return ctl::ctl_run(command).map_err(|e| e.into());
+    }
+
+    if let Some(profile) = args.profile.as_deref() {
+        // the name of a directory under profiles/, not a path
+        if profile.is_empty() || profile.contains(['/', '\\']) || profile == "." || profile == ".."
+        {
+            return Err(format!("Invalid profile name: {}", profile).into());
+        }
+        config_io::set_active_profile(Some(profile.into()));
+    }
+
+    if let Some(kind) = args.dump_schema {
+        return config_schema::dump_schema_cli(kind).map_err(|e| e.into());
+    }
+
+    if args.check_config {
+        if !config_check::check_config_cli() {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    #[cfg(feature = "headless")]
+    let headless = args.headless.is_some();
+    #[cfg(not(feature = "headless"))]
+    let headless = false;
+
+    // headless runs are for testing, and must leave a running instance alone
+    if !args.multi && !headless && !ensure_single_instance(args.replace) {
         println!("Looks like WlxOverlay-S is already running.");
         println!("Use --replace and I will terminate it for you.");
+        println!("Use the ctl subcommand to control the running instance.");
         return Ok(());
     }
 
@@ -85,6 +160,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     );
     log::info!("It is {}.", chrono::Local::now().format("%c"));
 
+    if let Some(profile) = config_io::get_active_profile() {
+        if config_io::list_profiles().contains(&profile) {
+            log::info!("Using profile {}.", profile);
+        } else {
+            log::warn!("Profile {} not found, using conf.d only.", profile);
+            config_io::set_active_profile(None);
+        }
+    }
+
     #[cfg(feature = "openvr")]
     if args.uninstall {
         crate::backend::openvr::openvr_uninstall();
@@ -105,18 +189,41 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     });
 
-    auto_run(running, args);
+    let mut input_session = match InputSession::new(args.record.as_deref(), args.replay.as_deref())
+    {
+        Ok(session) => session,
+        Err(e) => {
+            log::error!("Could not set up input recording/replay: {}", e);
+            return Err(e.into());
+        }
+    };
+
+    #[cfg(feature = "headless")]
+    if let Some(script_path) = args.headless.as_ref() {
+        if let Err(e) = crate::backend::headless::headless_run(
+            running,
+            script_path,
+            args.show,
+            &mut input_session,
+        ) {
+            log::error!("{}", e.to_string());
+            return Err(e.into());
+        }
+        return Ok(());
+    }
+
+    auto_run(running, args, &mut input_session);
 
     Ok(())
 }
 
-fn auto_run(running: Arc<AtomicBool>, args: Args) {
+fn auto_run(running: Arc<AtomicBool>, args: Args, input_session: &mut InputSession) {
     use backend::common::BackendError;
 
     #[cfg(feature = "openxr")]
     if !args_get_openvr(&args) {
         use crate::backend::openxr::openxr_run;
-        match openxr_run(running.clone(), args.show) {
+        match openxr_run(running.clone(), args.show, input_session) {
             Ok(()) => return,
             Err(BackendError::NotSupported) => (),
             Err(e) => {
@@ -129,7 +236,7 @@ fn auto_run(running: Arc<AtomicBool>, args: Args) {
     #[cfg(feature = "openvr")]
     if !args_get_openxr(&args) {
         use crate::backend::openvr::openvr_run;
-        match openvr_run(running.clone(), args.show) {
+        match openvr_run(running.clone(), args.show, input_session) {
             Ok(()) => return,
             Err(BackendError::NotSupported) => (),
             Err(e) => {
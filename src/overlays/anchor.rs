@@ -4,7 +4,8 @@ use std::sync::Arc;
 
 use crate::backend::overlay::{OverlayData, OverlayState, RelativeTo};
 use crate::config::{load_known_yaml, ConfigType};
-use crate::gui::modular::{modular_canvas, ModularUiConfig};
+use crate::gui::canvas::Canvas;
+use crate::gui::modular::{modular_canvas, ModularData, ModularUiConfig};
 use crate::state::AppState;
 
 pub static ANCHOR_NAME: Lazy<Arc<str>> = Lazy::new(|| Arc::from("anchor"));
@@ -27,7 +28,12 @@ where
             relative_to: RelativeTo::Stage,
             ..Default::default()
         },
-        backend: Box::new(modular_canvas(&config.size, &config.elements, state)?),
+        backend: Box::new(modular_canvas(&config, state)?),
         ..Default::default()
     })
 }
+
+pub fn create_anchor_canvas(state: &mut AppState) -> anyhow::Result<Canvas<(), ModularData>> {
+    let config = load_known_yaml::<ModularUiConfig>(ConfigType::Anchor);
+    modular_canvas(&config, state)
+}
@@ -0,0 +1,125 @@
+use std::rc::Rc;
+
+use rosc::OscType;
+
+use crate::{
+    backend::osc::OscSender,
+    hid::{KeyModifier, VirtualKey, XkbKeymap, META, SHIFT},
+};
+
+// VRChat truncates longer messages
+const CHATBOX_MAX_CHARS: usize = 144;
+
+/// Collects keystrokes from the keyboard and sends them to the VRChat chatbox via OSC.
+pub struct Chatbox {
+    pub active: bool,
+    buffer: String,
+    typing: bool,
+    sender: Option<Rc<OscSender>>,
+}
+
+impl Chatbox {
+    pub fn new(sender: Option<Rc<OscSender>>) -> Self {
+        if sender.is_none() {
+            log::warn!("No OSC sender, the chatbox will not function.");
+        }
+        Self {
+            active: false,
+            buffer: String::new(),
+            typing: false,
+            sender,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.buffer
+    }
+
+    pub fn toggle(&mut self) {
+        self.active = !self.active;
+        if !self.active {
+            self.buffer.clear();
+            self.set_typing(false);
+        }
+    }
+
+    pub fn key(&mut self, vk: VirtualKey, modifiers: KeyModifier, keymap: Option<&XkbKeymap>) {
+        match vk {
+            VirtualKey::Return | VirtualKey::KP_Enter => {
+                self.submit();
+                return;
+            }
+            VirtualKey::Escape => self.buffer.clear(),
+            VirtualKey::BackSpace => {
+                self.buffer.pop();
+            }
+            VirtualKey::Space => self.push(" "),
+            _ => {
+                let text = match keymap {
+                    Some(keymap) => keymap.label_for_key(vk, modifiers & (SHIFT | META)),
+                    None => fallback_text(vk, modifiers),
+                };
+                self.push(&text);
+            }
+        }
+        self.set_typing(!self.buffer.is_empty());
+    }
+
+    pub fn set_typing(&mut self, typing: bool) {
+        if self.typing == typing {
+            return;
+        }
+        self.typing = typing;
+        self.send("/chatbox/typing", vec![OscType::Bool(typing)]);
+    }
+
+    fn push(&mut self, text: &str) {
+        for c in text.chars().filter(|c| !c.is_control()) {
+            if self.buffer.chars().count() >= CHATBOX_MAX_CHARS {
+                break;
+            }
+            self.buffer.push(c);
+        }
+    }
+
+    fn submit(&mut self) {
+        if !self.buffer.is_empty() {
+            let text = std::mem::take(&mut self.buffer);
+            // send immediately, play notification sound
+            self.send(
+                "/chatbox/input",
+                vec![
+                    OscType::String(text),
+                    OscType::Bool(true),
+                    OscType::Bool(true),
+                ],
+            );
+        }
+        self.set_typing(false);
+    }
+
+    fn send(&self, addr: &str, args: Vec<OscType>) {
+        if let Some(sender) = self.sender.as_ref() {
+            if let Err(e) = sender.send_message(addr.into(), args) {
+                log::error!("Chatbox: {}", e);
+            }
+        }
+    }
+}
+
+/// Used when no keymap is available, covers letters and the number row
+fn fallback_text(vk: VirtualKey, modifiers: KeyModifier) -> String {
+    let name = format!("{:?}", vk);
+    let name = match name.strip_prefix('N') {
+        Some(digit) if digit.len() == 1 => digit,
+        _ => name.as_str(),
+    };
+    if name.len() != 1 {
+        return String::new();
+    }
+    if modifiers & SHIFT != 0 {
+        name.to_uppercase()
+    } else {
+        name.to_lowercase()
+    }
+}
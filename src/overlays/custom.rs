@@ -3,13 +3,14 @@ use std::sync::Arc;
 use glam::Vec3A;
 
 use crate::{
-    backend::overlay::{ui_transform, OverlayBackend, OverlayState},
+    backend::overlay::{ui_transform, OverlayBackend, OverlayState, WindowKind},
     config::{load_custom_ui, load_known_yaml, ConfigType},
+    config_check::{self, ConfigDiagnostic},
     gui::modular::{modular_canvas, ModularUiConfig},
     state::AppState,
 };
 
-const SETTINGS_NAME: &str = "settings";
+pub const SETTINGS_NAME: &str = "settings";
 
 pub fn create_custom(
     state: &mut AppState,
@@ -21,13 +22,14 @@ pub fn create_custom(
         match load_custom_ui(&name) {
             Ok(config) => config,
             Err(e) => {
-                log::error!("Failed to load custom UI config for {}: {:?}", name, e);
+                let file = format!("{}.yaml", name);
+                config_check::report(ConfigDiagnostic::from_error(&file, e));
                 return None;
             }
         }
     };
 
-    let canvas = match modular_canvas(&config.size, &config.elements, state) {
+    let canvas = match modular_canvas(&config, state) {
         Ok(canvas) => canvas,
         Err(e) => {
             log::error!("Failed to create canvas for {}: {:?}", name, e);
@@ -43,6 +45,7 @@ pub fn create_custom(
         spawn_scale: config.width,
         spawn_point: Vec3A::from_array(config.spawn_pos.unwrap_or([0., 0., -0.5])),
         interaction_transform: ui_transform(&config.size),
+        window_kind: Some(WindowKind::Ui),
         ..Default::default()
     };
     let backend = Box::new(canvas);
@@ -10,9 +10,11 @@ use crate::{
         overlay::{FrameTransform, OverlayBackend, OverlayData, OverlayRenderer, OverlayState},
     },
     config::{self, ConfigType},
+    config_check::{self, ConfigDiagnostic},
     gui::{
         canvas::{builder::CanvasBuilder, control::Control, Canvas},
-        color_parse, KeyCapType,
+        theme::{Palette, Theme},
+        KeyCapType,
     },
     hid::{
         get_key_type, KeyModifier, KeyType, VirtualKey, XkbKeymap, ALT, CTRL, KEYS_TO_MODS, META,
@@ -23,8 +25,12 @@ use crate::{
 use glam::{vec2, vec3a, Affine2, Vec4};
 use once_cell::sync::Lazy;
 use regex::Regex;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "osc")]
+use super::chatbox::Chatbox;
+
 const PIXELS_PER_UNIT: f32 = 80.;
 const BUTTON_PADDING: f32 = 4.;
 const AUTO_RELEASE_MODS: [KeyModifier; 5] = [SHIFT, CTRL, ALT, SUPER, META];
@@ -55,6 +61,12 @@ fn set_modifiers(app: &mut AppState, mods: u8) {
     }
 }
 
+/// Checks keyboard.yaml without falling back to the built-in layout.
+pub fn check_layout() -> anyhow::Result<()> {
+    let mut layout = config::try_load_known_yaml::<Layout>(ConfigType::Keyboard)?;
+    Ok(layout.post_load()?)
+}
+
 pub fn create_keyboard<O>(
     app: &AppState,
     mut keymap: Option<XkbKeymap>,
@@ -62,14 +74,28 @@ pub fn create_keyboard<O>(
 where
     O: Default,
 {
+    let layout = Layout::load_from_disk();
+
+    #[cfg(feature = "osc")]
+    let chatbox = app
+        .session
+        .config
+        .keyboard_chatbox
+        .then(|| Chatbox::new(app.osc_sender.clone()));
+
+    #[cfg(feature = "osc")]
+    let extra_rows = if chatbox.is_some() { 1 } else { 0 };
+    #[cfg(not(feature = "osc"))]
+    let extra_rows = 0;
+
     let size = vec2(
-        LAYOUT.row_size * PIXELS_PER_UNIT,
-        (LAYOUT.main_layout.len() as f32) * PIXELS_PER_UNIT,
+        layout.row_size * PIXELS_PER_UNIT,
+        ((layout.main_layout.len() + extra_rows) as f32) * PIXELS_PER_UNIT,
     );
 
     let data = KeyboardData {
         modifiers: 0,
-        alt_modifier: match LAYOUT.alt_modifier {
+        alt_modifier: match layout.alt_modifier {
             AltModifier::Shift => SHIFT,
             AltModifier::Ctrl => CTRL,
             AltModifier::Alt => ALT,
@@ -78,6 +104,10 @@ where
             _ => 0,
         },
         processes: vec![],
+        #[cfg(feature = "osc")]
+        chatbox,
+        #[cfg(feature = "osc")]
+        keymap: keymap.clone(),
     };
 
     let mut canvas = CanvasBuilder::new(
@@ -88,32 +118,59 @@ where
         data,
     )?;
 
-    canvas.bg_color = color_parse("#181926").unwrap(); //safe
+    let theme = Theme::from_config(&app.session.config).with_overrides(&layout.palette);
+    canvas.bg_color = theme.parse_or_default("$bg_dark");
     canvas.panel(0., 0., size.x, size.y, 12.);
 
     canvas.font_size = 18;
-    canvas.fg_color = color_parse("#cad3f5").unwrap(); //safe
-    canvas.bg_color = color_parse("#1e2030").unwrap(); //safe
+    canvas.fg_color = theme.parse_or_default("$fg");
+    canvas.bg_color = theme.parse_or_default("$bg_alt");
 
     let has_altgr = keymap.as_ref().map_or(false, |k| k.has_altgr());
 
-    if !LAYOUT.auto_labels.unwrap_or(true) {
+    if !layout.auto_labels.unwrap_or(true) {
         keymap = None;
     }
 
-    let unit_size = size.x / LAYOUT.row_size;
+    let unit_size = size.x / layout.row_size;
     let h = unit_size - 2. * BUTTON_PADDING;
 
-    for row in 0..LAYOUT.key_sizes.len() {
-        let y = unit_size * (row as f32) + BUTTON_PADDING;
+    if extra_rows > 0 {
+        let toggle_w = unit_size * 2. - 2. * BUTTON_PADDING;
+        let preview = canvas.label(
+            BUTTON_PADDING * 2.,
+            BUTTON_PADDING,
+            size.x - toggle_w - BUTTON_PADDING * 4.,
+            h,
+            12.,
+            "".into(),
+        );
+        preview.on_update = Some(chatbox_preview_update);
+
+        let button = canvas.key_button(
+            size.x - toggle_w - BUTTON_PADDING,
+            BUTTON_PADDING,
+            toggle_w,
+            h,
+            12.,
+            KeyCapType::Regular,
+            &["Chat".into()],
+        );
+        button.state = Some(KeyButtonData::ChatboxToggle);
+        button.on_press = Some(key_press);
+        button.test_highlight = Some(test_highlight);
+    }
+
+    for row in 0..layout.key_sizes.len() {
+        let y = unit_size * ((row + extra_rows) as f32) + BUTTON_PADDING;
         let mut sum_size = 0f32;
 
-        for col in 0..LAYOUT.key_sizes[row].len() {
-            let my_size = LAYOUT.key_sizes[row][col];
+        for col in 0..layout.key_sizes[row].len() {
+            let my_size = layout.key_sizes[row][col];
             let x = unit_size * sum_size + BUTTON_PADDING;
             let w = unit_size * my_size - 2. * BUTTON_PADDING;
 
-            if let Some(key) = LAYOUT.main_layout[row][col].as_ref() {
+            if let Some(key) = layout.main_layout[row][col].as_ref() {
                 let mut label = Vec::with_capacity(2);
                 let mut maybe_state: Option<KeyButtonData> = None;
                 let mut cap_type = KeyCapType::Regular;
@@ -159,11 +216,11 @@ where
                     } else {
                         maybe_state = Some(KeyButtonData::Key { vk, pressed: false });
                     }
-                } else if let Some(macro_verbs) = LAYOUT.macros.get(key) {
+                } else if let Some(macro_verbs) = layout.macros.get(key) {
                     maybe_state = Some(KeyButtonData::Macro {
                         verbs: key_events_for_macro(macro_verbs),
                     });
-                } else if let Some(exec_args) = LAYOUT.exec_commands.get(key) {
+                } else if let Some(exec_args) = layout.exec_commands.get(key) {
                     if exec_args.is_empty() {
                         log::error!("Keyboard: EXEC args empty for {}", key);
                         continue;
@@ -183,7 +240,7 @@ where
 
                 if let Some(state) = maybe_state {
                     if label.is_empty() {
-                        label = LAYOUT.label_for_key(key);
+                        label = layout.label_for_key(key);
                     }
                     let button = canvas.key_button(x, y, w, h, 12., cap_type, &label);
                     button.state = Some(state);
@@ -202,7 +259,7 @@ where
     let interaction_transform = Affine2::from_translation(vec2(0.5, 0.5))
         * Affine2::from_scale(vec2(1., -size.x as f32 / size.y as f32));
 
-    let width = LAYOUT.row_size * 0.05 * app.session.config.keyboard_scale;
+    let width = layout.row_size * 0.05 * app.session.config.keyboard_scale;
 
     Ok(OverlayData {
         state: OverlayState {
@@ -237,6 +294,13 @@ fn key_press(
                 _ => 0,
             };
 
+            #[cfg(feature = "osc")]
+            if let Some(chatbox) = data.chatbox.as_mut().filter(|c| c.active) {
+                chatbox.key(*vk, data.modifiers, data.keymap.as_ref());
+                *pressed = true;
+                return;
+            }
+
             app.hid_provider.set_modifiers(data.modifiers);
 
             send_key(app, *vk, true);
@@ -246,10 +310,16 @@ fn key_press(
             *sticky = data.modifiers & *modifier == 0;
             data.modifiers |= *modifier;
             data.key_click(app);
-            set_modifiers(app, data.modifiers);
+            if !data.chatbox_active() {
+                set_modifiers(app, data.modifiers);
+            }
         }
         Some(KeyButtonData::Macro { verbs }) => {
             data.key_click(app);
+            // would go to the focused window instead of the chatbox
+            if data.chatbox_active() {
+                return;
+            }
             for (vk, press) in verbs {
                 send_key(app, *vk, *press);
             }
@@ -260,10 +330,20 @@ fn key_press(
                 .retain_mut(|child| !matches!(child.try_wait(), Ok(Some(_))));
 
             data.key_click(app);
+            if data.chatbox_active() {
+                return;
+            }
             if let Ok(child) = Command::new(program).args(args).spawn() {
                 data.processes.push(child);
             }
         }
+        Some(KeyButtonData::ChatboxToggle) => {
+            data.key_click(app);
+            #[cfg(feature = "osc")]
+            if let Some(chatbox) = data.chatbox.as_mut() {
+                chatbox.toggle();
+            }
+        }
         None => {}
     }
 }
@@ -275,20 +355,27 @@ fn key_release(
 ) {
     match control.state.as_mut() {
         Some(KeyButtonData::Key { vk, pressed }) => {
-            send_key(app, *vk, false);
+            let chatbox_active = data.chatbox_active();
+            if !chatbox_active {
+                send_key(app, *vk, false);
+            }
             *pressed = false;
 
             for m in AUTO_RELEASE_MODS.iter() {
                 if data.modifiers & *m != 0 {
                     data.modifiers &= !*m;
-                    set_modifiers(app, data.modifiers);
+                    if !chatbox_active {
+                        set_modifiers(app, data.modifiers);
+                    }
                 }
             }
         }
         Some(KeyButtonData::Modifier { modifier, sticky }) => {
             if !*sticky {
                 data.modifiers &= !*modifier;
-                set_modifiers(app, data.modifiers);
+                if !data.chatbox_active() {
+                    set_modifiers(app, data.modifiers);
+                }
             }
         }
         Some(KeyButtonData::Exec {
@@ -300,7 +387,7 @@ fn key_release(
             data.processes
                 .retain_mut(|child| !matches!(child.try_wait(), Ok(Some(_))));
 
-            if let Some(program) = release_program {
+            if let Some(program) = release_program.as_ref().filter(|_| !data.chatbox_active()) {
                 if let Ok(child) = Command::new(program).args(release_args).spawn() {
                     data.processes.push(child);
                 }
@@ -320,6 +407,7 @@ fn test_highlight(
     let pressed = match control.state.as_ref() {
         Some(KeyButtonData::Key { pressed, .. }) => *pressed,
         Some(KeyButtonData::Modifier { modifier, .. }) => data.modifiers & *modifier != 0,
+        Some(KeyButtonData::ChatboxToggle) => data.chatbox_active(),
         _ => false,
     };
 
@@ -334,6 +422,10 @@ struct KeyboardData {
     modifiers: KeyModifier,
     alt_modifier: KeyModifier,
     processes: Vec<Child>,
+    #[cfg(feature = "osc")]
+    chatbox: Option<Chatbox>,
+    #[cfg(feature = "osc")]
+    keymap: Option<XkbKeymap>,
 }
 
 const KEY_AUDIO_WAV: &[u8] = include_bytes!("../res/421581.wav");
@@ -344,6 +436,32 @@ impl KeyboardData {
             app.audio.play(KEY_AUDIO_WAV);
         }
     }
+
+    fn chatbox_active(&self) -> bool {
+        #[cfg(feature = "osc")]
+        let ret = self.chatbox.as_ref().is_some_and(|c| c.active);
+
+        #[cfg(not(feature = "osc"))]
+        let ret = false;
+
+        ret
+    }
+}
+
+#[allow(unused_variables)] // needed in case if osc feature is not enabled
+fn chatbox_preview_update(
+    control: &mut Control<KeyboardData, KeyButtonData>,
+    data: &mut KeyboardData,
+    _app: &mut AppState,
+) {
+    #[cfg(feature = "osc")]
+    if let Some(chatbox) = data.chatbox.as_ref() {
+        if chatbox.active {
+            control.set_text(&format!("{}_", chatbox.text()));
+        } else {
+            control.set_text("");
+        }
+    }
 }
 
 enum KeyButtonData {
@@ -364,14 +482,13 @@ enum KeyButtonData {
         release_program: Option<String>,
         release_args: Vec<String>,
     },
+    ChatboxToggle,
 }
 
-static LAYOUT: Lazy<Layout> = Lazy::new(Layout::load_from_disk);
-
 static MACRO_REGEX: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"^([A-Za-z0-9_-]+)(?: +(UP|DOWN))?$").unwrap()); // want panic
 
-#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, JsonSchema)]
 #[repr(usize)]
 pub enum AltModifier {
     None,
@@ -382,7 +499,7 @@ pub enum AltModifier {
     Meta,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct Layout {
     name: String,
     row_size: f32,
@@ -393,39 +510,53 @@ pub struct Layout {
     macros: HashMap<String, Vec<String>>,
     labels: HashMap<String, Vec<String>>,
     auto_labels: Option<bool>,
+    /// Overrides theme colors for the keyboard only
+    #[serde(default)]
+    palette: Palette,
 }
 
 impl Layout {
     fn load_from_disk() -> Layout {
         let mut layout = config::load_known_yaml::<Layout>(ConfigType::Keyboard);
-        layout.post_load();
+        if let Err(diag) = layout.post_load() {
+            config_check::report(diag);
+            layout = config::load_builtin_yaml::<Layout>(ConfigType::Keyboard);
+        }
         layout
     }
 
-    fn post_load(&mut self) {
+    fn post_load(&mut self) -> Result<(), ConfigDiagnostic> {
         for i in 0..self.key_sizes.len() {
             let row = &self.key_sizes[i];
             let width: f32 = row.iter().sum();
             if (width - self.row_size).abs() > 0.001 {
-                panic!(
-                    "Row {} has a width of {}, but the row size is {}",
-                    i, width, self.row_size
-                );
+                return Err(ConfigDiagnostic::new(
+                    "keyboard.yaml",
+                    format!(
+                        "Row {} has a width of {}, but the row size is {}",
+                        i, width, self.row_size
+                    ),
+                )
+                .with_field(format!("key_sizes[{}]", i)));
             }
         }
 
         for i in 0..self.main_layout.len() {
             let row = &self.main_layout[i];
             let width = row.len();
-            if width != self.key_sizes[i].len() {
-                panic!(
-                    "Row {} has {} keys, needs to have {} according to key_sizes",
-                    i,
-                    width,
-                    self.key_sizes[i].len()
-                );
+            let expected = self.key_sizes.get(i).map_or(0, Vec::len);
+            if width != expected {
+                return Err(ConfigDiagnostic::new(
+                    "keyboard.yaml",
+                    format!(
+                        "Row {} has {} keys, needs to have {} according to key_sizes",
+                        i, width, expected
+                    ),
+                )
+                .with_field(format!("main_layout[{}]", i)));
             }
         }
+        Ok(())
     }
 
     fn label_for_key(&self, key: &str) -> Vec<String> {
@@ -545,6 +676,10 @@ impl OverlayRenderer for KeyboardBackend {
         self.canvas.view()
     }
     fn pause(&mut self, app: &mut AppState) -> anyhow::Result<()> {
+        #[cfg(feature = "osc")]
+        if let Some(chatbox) = self.canvas.data_mut().chatbox.as_mut() {
+            chatbox.set_typing(false);
+        }
         self.canvas.data_mut().modifiers = 0;
         set_modifiers(app, 0);
         self.canvas.pause(app)
@@ -11,7 +11,7 @@ use crate::{
         common::OverlaySelector,
         overlay::{
             ui_transform, FrameTransform, OverlayBackend, OverlayRenderer, OverlayState,
-            SplitOverlayBackend,
+            SplitOverlayBackend, WindowKind,
         },
         task::TaskType,
     },
@@ -140,6 +140,7 @@ pub fn new_mirror(
         show_hide,
         want_visible: true,
         spawn_scale: 0.5 * session.config.desktop_view_scale,
+        window_kind: Some(WindowKind::Mirror),
         ..Default::default()
     };
     let backend = Box::new(SplitOverlayBackend {
@@ -1,4 +1,6 @@
 pub mod anchor;
+#[cfg(feature = "osc")]
+pub mod chatbox;
 pub mod custom;
 pub mod keyboard;
 #[cfg(feature = "wayland")]
@@ -1,31 +1,252 @@
-use std::{f32::consts::PI, ops::Add, sync::Arc, time::Instant};
+use std::{
+    collections::VecDeque,
+    f32::consts::PI,
+    ops::Add,
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use glam::{vec3a, Quat};
+use chrono::{DateTime, Local};
+use glam::{vec3a, Quat, Vec3A, Vec4};
 use idmap_derive::IntegerId;
-use once_cell::sync::Lazy;
+use image::{DynamicImage, RgbaImage};
+use regex::Regex;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use vulkano::{command_buffer::CommandBufferUsage, format::Format, image::view::ImageView};
 
 use crate::{
     backend::{
         common::OverlaySelector,
-        overlay::{OverlayBackend, OverlayState, RelativeTo},
-        task::TaskType,
+        input::{Haptics, InteractionHandler, PointerHit, PointerMode},
+        notifications::{discard_served, CloseReason},
+        overlay::{OverlayBackend, OverlayID, OverlayState, RelativeTo, SplitOverlayBackend},
+        task::{TaskContainer, TaskType},
+    },
+    config::{self, AStrMapExt},
+    config_check::{self, ConfigDiagnostic},
+    config_io,
+    gui::{
+        canvas::{builder::CanvasBuilder, control::Control},
+        modular::{
+            component::expand_elements, label::LabelContent, modular_canvas, ModularElement,
+            ModularUiConfig,
+        },
+        theme::Theme,
     },
-    gui::{canvas::builder::CanvasBuilder, color_parse},
+    overlays::keyboard::KEYBOARD_NAME,
     state::{AppState, LeftRight},
 };
 
 const FONT_SIZE: isize = 16;
 const PADDING: (f32, f32) = (25., 7.);
 const PIXELS_TO_METERS: f32 = 1. / 2000.;
+const ACTION_BUTTON_HEIGHT: f32 = 36.;
+const ACTION_GAP: f32 = 8.;
+const ICON_SIZE: u32 = 64;
 const TOAST_AUDIO_WAV: &[u8] = include_bytes!("../res/557297.wav");
-static TOAST_NAME: Lazy<Arc<str>> = Lazy::new(|| "toast".into());
+const STACK_GAP: f32 = 0.01;
+pub const TOAST_NAME: &str = "toast";
+const TOAST_TEMPLATE_FILE: &str = "toast.yaml";
+const DEFAULT_TIMEOUT: f32 = 3.0;
+/// Longest timeout accepted from outside sources, one day
+pub const MAX_TIMEOUT: f32 = 24. * 60. * 60.;
+
+/// Timeouts from sockets, D-Bus or the config need to pass this before use
+pub fn is_valid_timeout(seconds: f32) -> bool {
+    seconds.is_finite() && seconds > 0. && seconds <= MAX_TIMEOUT
+}
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum DisplayMethod {
     Hide,
     Center,
     Watch,
+    /// Head-locked, in a corner of the view
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    /// Same spot as the watch, on the given hand
+    LeftHand,
+    RightHand,
+    /// Spawns in front of the head, then stays in place
+    World,
+}
+
+/// Where a toast goes, and which way older toasts get pushed to make room
+struct ToastPlacement {
+    spawn_point: Vec3A,
+    spawn_rotation: Quat,
+    relative_to: RelativeTo,
+    stack_dir: Vec3A,
+}
+
+impl ToastPlacement {
+    fn new(method: DisplayMethod, app: &AppState) -> Option<Self> {
+        let head = |x: f32, y: f32, stack_dir: Vec3A| ToastPlacement {
+            spawn_point: vec3a(x, y, -0.6),
+            spawn_rotation: Quat::IDENTITY,
+            relative_to: RelativeTo::Head,
+            stack_dir,
+        };
+
+        Some(match method {
+            DisplayMethod::Hide => return None,
+            DisplayMethod::Center => ToastPlacement {
+                spawn_point: vec3a(0., -0.2, -0.5),
+                ..head(0., 0., Vec3A::NEG_Y)
+            },
+            DisplayMethod::TopLeft => head(-0.25, 0.15, Vec3A::NEG_Y),
+            DisplayMethod::TopRight => head(0.25, 0.15, Vec3A::NEG_Y),
+            DisplayMethod::BottomLeft => head(-0.25, -0.2, Vec3A::Y),
+            DisplayMethod::BottomRight => head(0.25, -0.2, Vec3A::Y),
+            DisplayMethod::World => ToastPlacement {
+                relative_to: RelativeTo::None,
+                ..head(0., -0.2, Vec3A::NEG_Y)
+            },
+            DisplayMethod::Watch => Self::hand(app.session.config.watch_hand, app),
+            DisplayMethod::LeftHand => Self::hand(LeftRight::Left, app),
+            DisplayMethod::RightHand => Self::hand(LeftRight::Right, app),
+        })
+    }
+
+    /// Width and height are in meters
+    fn overlay_state(
+        &self,
+        name: Arc<str>,
+        method: DisplayMethod,
+        width: f32,
+        height: f32,
+    ) -> (OverlayState, StackedToast) {
+        let state = OverlayState {
+            name,
+            want_visible: true,
+            spawn_scale: width,
+            spawn_rotation: self.spawn_rotation,
+            spawn_point: self.spawn_point,
+            z_order: 70,
+            relative_to: self.relative_to,
+            interactable: true,
+            ..Default::default()
+        };
+        let stacked = StackedToast {
+            overlay: state.id,
+            method,
+            base: self.spawn_point,
+            dir: self.stack_dir,
+            height,
+            offset: 0.,
+        };
+        (state, stacked)
+    }
+
+    fn hand(hand: LeftRight, app: &AppState) -> Self {
+        let mut watch_pos = app.session.config.watch_pos + vec3a(-0.005, -0.05, 0.02);
+        let mut watch_rot = app.session.config.watch_rot;
+        let relative_to = match hand {
+            LeftRight::Left => RelativeTo::Hand(0),
+            LeftRight::Right => {
+                watch_pos.x = -watch_pos.x;
+                watch_rot = watch_rot * Quat::from_rotation_x(PI) * Quat::from_rotation_z(PI);
+                RelativeTo::Hand(1)
+            }
+        };
+        ToastPlacement {
+            spawn_point: watch_pos,
+            spawn_rotation: watch_rot,
+            relative_to,
+            stack_dir: watch_rot * Vec3A::Y,
+        }
+    }
+}
+
+struct StackedToast {
+    overlay: OverlayID,
+    method: DisplayMethod,
+    base: Vec3A,
+    dir: Vec3A,
+    height: f32,
+    offset: f32,
+}
+
+/// Toasts currently on screen, newest first
+#[derive(Default)]
+pub struct ToastStack {
+    toasts: VecDeque<StackedToast>,
+    serial: usize,
+}
+
+impl ToastStack {
+    fn next_name(&mut self) -> Arc<str> {
+        self.serial += 1;
+        format!("{}-{}", TOAST_NAME, self.serial).into()
+    }
+
+    /// Returns the toasts that no longer fit
+    fn push(&mut self, toast: StackedToast, max_len: usize) -> Vec<OverlayID> {
+        self.toasts.push_front(toast);
+        self.toasts
+            .drain(max_len.max(1).min(self.toasts.len())..)
+            .map(|t| t.overlay)
+            .collect()
+    }
+
+    fn remove(&mut self, overlay: OverlayID) -> bool {
+        let len = self.toasts.len();
+        self.toasts.retain(|t| t.overlay != overlay);
+        self.toasts.len() != len
+    }
+
+    fn relayout(&mut self, tasks: &mut TaskContainer) {
+        // (method, offset, height) of the previous toast in each stack
+        let mut last: Vec<(DisplayMethod, f32, f32)> = vec![];
+
+        for t in self.toasts.iter_mut() {
+            let offset = match last.iter_mut().find(|(m, _, _)| *m == t.method) {
+                Some((_, offset, height)) => {
+                    *offset += (*height + t.height) * 0.5 + STACK_GAP;
+                    *height = t.height;
+                    *offset
+                }
+                None => {
+                    last.push((t.method, 0., t.height));
+                    0.
+                }
+            };
+            if offset == t.offset {
+                continue;
+            }
+
+            let (base, dir, delta) = (t.base, t.dir, offset - t.offset);
+            let world = t.method == DisplayMethod::World;
+            t.offset = offset;
+            tasks.enqueue(TaskType::Overlay(
+                OverlaySelector::Id(t.overlay),
+                Box::new(move |_, o| {
+                    if world {
+                        let dir = (o.transform.matrix3 * dir).normalize();
+                        o.transform.translation += dir * delta;
+                    } else {
+                        o.spawn_point = base + dir * offset;
+                    }
+                    o.dirty = true;
+                }),
+            ));
+        }
+    }
+}
+
+/// Removes a toast from the screen, moving the rest of its stack into place
+pub fn dismiss_toast(app: &mut AppState, overlay: OverlayID, reason: CloseReason) {
+    if !app.toast_stack.remove(overlay) {
+        return;
+    }
+    app.tasks
+        .enqueue(TaskType::DropOverlay(OverlaySelector::Id(overlay)));
+    app.toast_stack.relayout(&mut app.tasks);
+    app.served_notifications.toast_closed(overlay, reason);
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, IntegerId, Serialize, Deserialize)]
@@ -36,6 +257,176 @@ pub enum ToastTopic {
     IpdChange,
 }
 
+impl ToastTopic {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ToastTopic::System => "System",
+            ToastTopic::DesktopNotification => "Desktop",
+            ToastTopic::XSNotification => "XSOverlay",
+            ToastTopic::IpdChange => "IPD",
+        }
+    }
+}
+
+/// Matches notifications by regex and overrides how they are displayed.
+/// All given patterns must match. The first matching rule applies.
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub struct NotificationRule {
+    pub app_name: Option<Arc<str>>,
+    pub summary: Option<Arc<str>>,
+    pub body: Option<Arc<str>>,
+    pub urgency: Option<u8>,
+
+    pub display: Option<DisplayMethod>,
+    pub timeout: Option<f32>,
+    pub sound: Option<bool>,
+    /// Read out the toast with text-to-speech, overrides notification_tts_topics
+    pub tts: Option<bool>,
+    /// Do not show the toast at all. It will still be added to the history.
+    #[serde(default)]
+    pub mute: bool,
+}
+
+pub struct ToastRule {
+    app_name: Option<Regex>,
+    summary: Option<Regex>,
+    body: Option<Regex>,
+    rule: NotificationRule,
+}
+
+impl ToastRule {
+    pub fn new(rule: &NotificationRule) -> anyhow::Result<Self> {
+        let compile = |pattern: &Option<Arc<str>>| -> anyhow::Result<Option<Regex>> {
+            Ok(match pattern {
+                Some(p) => Some(Regex::new(p)?),
+                None => None,
+            })
+        };
+
+        if rule.timeout.is_some_and(|t| !is_valid_timeout(t)) {
+            anyhow::bail!("timeout must be between 0 and {} seconds", MAX_TIMEOUT);
+        }
+
+        Ok(Self {
+            app_name: compile(&rule.app_name)?,
+            summary: compile(&rule.summary)?,
+            body: compile(&rule.body)?,
+            rule: rule.clone(),
+        })
+    }
+
+    /// Rules that fail to compile or have an invalid timeout are reported and left out.
+    pub fn compile_all(rules: &[NotificationRule]) -> Vec<Self> {
+        rules
+            .iter()
+            .enumerate()
+            .filter_map(|(i, rule)| {
+                ToastRule::new(rule)
+                    .map_err(|e| {
+                        config_check::report(
+                            ConfigDiagnostic::new(
+                                &config::general_file_setting("notification_rules"),
+                                format!("{}, ignoring rule", e),
+                            )
+                            .with_field(format!("notification_rules[{}]", i)),
+                        )
+                    })
+                    .ok()
+            })
+            .collect()
+    }
+
+    fn matches(&self, toast: &Toast) -> bool {
+        let test = |re: &Option<Regex>, text: &str| re.as_ref().map_or(true, |r| r.is_match(text));
+
+        test(&self.app_name, &toast.app_name)
+            && test(&self.summary, &toast.title)
+            && test(&self.body, &toast.body)
+            && self.rule.urgency.map_or(true, |u| u == toast.urgency)
+    }
+}
+
+pub struct ToastHistoryEntry {
+    pub time: DateTime<Local>,
+    pub topic: ToastTopic,
+    pub title: Arc<str>,
+    pub body: Arc<str>,
+}
+
+/// Most recent toasts, newest first
+#[derive(Default)]
+pub struct ToastHistory {
+    entries: VecDeque<ToastHistoryEntry>,
+    /// Incremented on every change, so that UI can tell when to refresh
+    pub serial: u64,
+}
+
+impl ToastHistory {
+    pub fn push(&mut self, entry: ToastHistoryEntry, max_len: usize) {
+        self.entries.push_front(entry);
+        self.entries.truncate(max_len);
+        self.serial += 1;
+    }
+
+    pub fn get(&self, idx: usize) -> Option<&ToastHistoryEntry> {
+        self.entries.get(idx)
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.serial += 1;
+    }
+}
+
+/// Decoded RGBA pixels, scaled down to fit the toast.
+/// Decoded off the main thread as the notification arrives, uploaded to the GPU in new_toast.
+pub struct ToastIcon {
+    width: u32,
+    height: u32,
+    rgba: Vec<u8>,
+}
+
+impl ToastIcon {
+    fn from_image(image: DynamicImage) -> Self {
+        let image = image.thumbnail(ICON_SIZE, ICON_SIZE).to_rgba8();
+        Self {
+            width: image.width(),
+            height: image.height(),
+            rgba: image.into_raw(),
+        }
+    }
+
+    /// PNG or JPEG data
+    pub fn from_encoded(data: &[u8]) -> anyhow::Result<Self> {
+        Ok(Self::from_image(image::load_from_memory(data)?))
+    }
+
+    pub fn from_path(path: &Path) -> anyhow::Result<Self> {
+        Ok(Self::from_image(image::open(path)?))
+    }
+
+    pub fn from_rgba(width: u32, height: u32, rgba: Vec<u8>) -> anyhow::Result<Self> {
+        let Some(image) = RgbaImage::from_raw(width, height, rgba) else {
+            anyhow::bail!("Icon data does not match its size of {}x{}", width, height);
+        };
+        Ok(Self::from_image(DynamicImage::ImageRgba8(image)))
+    }
+
+    fn upload(&self, app: &mut AppState) -> anyhow::Result<Arc<ImageView>> {
+        let mut command_buffer = app
+            .graphics
+            .create_command_buffer(CommandBufferUsage::OneTimeSubmit)?;
+        let image = command_buffer.texture2d_raw(
+            self.width,
+            self.height,
+            Format::R8G8B8A8_UNORM,
+            &self.rgba,
+        )?;
+        command_buffer.build_and_execute_now()?;
+        Ok(ImageView::new_default(image)?)
+    }
+}
+
 pub struct Toast {
     pub title: Arc<str>,
     pub body: Arc<str>,
@@ -43,6 +434,15 @@ pub struct Toast {
     pub timeout: f32,
     pub sound: bool,
     pub topic: ToastTopic,
+    pub app_name: Arc<str>,
+    /// 0: low, 1: normal, 2: critical
+    pub urgency: u8,
+    pub display: Option<DisplayMethod>,
+    /// Set if we received this as the desktop notification server
+    pub dbus_id: Option<u32>,
+    /// Action key and label pairs, as sent by the client
+    pub actions: Vec<(Arc<str>, Arc<str>)>,
+    pub icon: Option<ToastIcon>,
 }
 
 #[allow(dead_code)]
@@ -52,11 +452,33 @@ impl Toast {
             title,
             body,
             opacity: 1.0,
-            timeout: 3.0,
+            timeout: DEFAULT_TIMEOUT,
             sound: false,
             topic,
+            app_name: "".into(),
+            urgency: 1,
+            display: None,
+            dbus_id: None,
+            actions: vec![],
+            icon: None,
         }
     }
+    pub fn with_app_name(mut self, app_name: Arc<str>) -> Self {
+        self.app_name = app_name;
+        self
+    }
+    pub fn with_urgency(mut self, urgency: u8) -> Self {
+        self.urgency = urgency;
+        self
+    }
+    pub fn with_actions(mut self, actions: Vec<(Arc<str>, Arc<str>)>) -> Self {
+        self.actions = actions;
+        self
+    }
+    pub fn with_icon(mut self, icon: Option<ToastIcon>) -> Self {
+        self.icon = icon;
+        self
+    }
     pub fn with_timeout(mut self, timeout: f32) -> Self {
         self.timeout = timeout;
         self
@@ -72,38 +494,109 @@ impl Toast {
     pub fn submit(self, app: &mut AppState) {
         self.submit_at(app, Instant::now());
     }
-    pub fn submit_at(self, app: &mut AppState, instant: Instant) {
-        let selector = OverlaySelector::Name(TOAST_NAME.clone());
+    pub fn submit_at(mut self, app: &mut AppState, instant: Instant) {
+        let mut mute = false;
+        let mut tts = None;
+        if let Some(rule) = app.session.toast_rules.iter().find(|r| r.matches(&self)) {
+            let rule = &rule.rule;
+            self.display = rule.display.or(self.display);
+            self.timeout = rule.timeout.unwrap_or(self.timeout);
+            self.sound = rule.sound.unwrap_or(self.sound);
+            tts = rule.tts;
+            mute = rule.mute;
+        }
 
-        let destroy_at = instant.add(std::time::Duration::from_secs_f32(self.timeout));
+        let timeout = Duration::try_from_secs_f32(self.timeout)
+            .ok()
+            .filter(|_| is_valid_timeout(self.timeout))
+            .unwrap_or_else(|| {
+                log::warn!("Invalid toast timeout: {}", self.timeout);
+                Duration::from_secs_f32(DEFAULT_TIMEOUT)
+            });
+        let destroy_at = instant.add(timeout);
 
         let has_sound = self.sound && app.session.config.notifications_sound_enabled;
 
-        // drop any toast that was created before us.
-        // (DropOverlay only drops overlays that were
-        // created before current frame)
-        app.tasks
-            .enqueue_at(TaskType::DropOverlay(selector.clone()), instant);
+        app.toast_history.push(
+            ToastHistoryEntry {
+                time: Local::now(),
+                topic: self.topic,
+                title: self.title.clone(),
+                body: self.body.clone(),
+            },
+            app.session.config.notification_history_size,
+        );
+
+        if mute {
+            if let Some(dbus_id) = self.dbus_id {
+                discard_served(app, dbus_id);
+            }
+            return;
+        }
+
+        let tts = tts.unwrap_or_else(|| {
+            let topics = &app.session.config.notification_tts_topics;
+            topics.get(self.topic).copied().unwrap_or(false)
+        });
+        if tts {
+            let text = if self.body.is_empty() {
+                self.title.to_string()
+            } else {
+                format!("{}. {}", self.title, self.body)
+            };
+            let command = app.session.config.notification_tts_command.clone();
+            app.tts.speak(text, command);
+        }
+
+        let name = app.toast_stack.next_name();
 
-        // CreateOverlay only creates the overlay if
-        // the selector doesn't exist yet, so in case
-        // multiple toasts are submitted for the same
-        // frame, only the first one gets created
         app.tasks.enqueue_at(
             TaskType::CreateOverlay(
-                selector.clone(),
+                OverlaySelector::Name(name.clone()),
                 Box::new(move |app| {
-                    let mut maybe_toast = new_toast(self, app);
-                    if let Some((state, _)) = maybe_toast.as_mut() {
+                    let dbus_id = self.dbus_id;
+                    let Some((mut state, backend, stacked)) = new_toast(self, name, app) else {
+                        // hidden, or failed to create
+                        if let Some(dbus_id) = dbus_id {
+                            discard_served(app, dbus_id);
+                        }
+                        return None;
+                    };
+                    if stacked.method == DisplayMethod::World {
+                        state.reset(app, false);
+                    } else {
                         state.auto_movement(app);
-                        app.tasks.enqueue_at(
-                            // at timeout, drop the overlay by ID instead
-                            // in order to avoid dropping any newer toasts
-                            TaskType::DropOverlay(OverlaySelector::Id(state.id)),
-                            destroy_at,
-                        );
                     }
-                    maybe_toast
+                    let overlay = state.id;
+
+                    // at timeout, drop the overlay by ID
+                    app.tasks.enqueue_at(
+                        TaskType::DropOverlay(OverlaySelector::Id(overlay)),
+                        destroy_at,
+                    );
+                    app.tasks.enqueue_at(
+                        TaskType::Global(Box::new(move |app| {
+                            dismiss_toast(app, overlay, CloseReason::Expired);
+                        })),
+                        destroy_at,
+                    );
+
+                    if let Some(dbus_id) = dbus_id {
+                        if let Some(replaced) = app.served_notifications.shown(dbus_id, overlay) {
+                            dismiss_toast(app, replaced, CloseReason::Undefined);
+                        }
+                    }
+
+                    let max_len = app.session.config.notification_max_toasts;
+                    for old in app.toast_stack.push(stacked, max_len) {
+                        app.tasks
+                            .enqueue(TaskType::DropOverlay(OverlaySelector::Id(old)));
+                        app.served_notifications
+                            .toast_closed(old, CloseReason::Undefined);
+                    }
+                    app.toast_stack.relayout(&mut app.tasks);
+
+                    Some((state, backend))
                 }),
             ),
             instant,
@@ -115,31 +608,37 @@ impl Toast {
     }
 }
 
-fn new_toast(toast: Toast, app: &mut AppState) -> Option<(OverlayState, Box<dyn OverlayBackend>)> {
-    let current_method = app
-        .session
-        .toast_topics
-        .get(toast.topic)
-        .copied()
-        .unwrap_or(DisplayMethod::Hide);
-
-    let (spawn_point, spawn_rotation, relative_to) = match current_method {
-        DisplayMethod::Hide => return None,
-        DisplayMethod::Center => (vec3a(0., -0.2, -0.5), Quat::IDENTITY, RelativeTo::Head),
-        DisplayMethod::Watch => {
-            let mut watch_pos = app.session.config.watch_pos + vec3a(-0.005, -0.05, 0.02);
-            let mut watch_rot = app.session.config.watch_rot;
-            let relative_to = match app.session.config.watch_hand {
-                LeftRight::Left => RelativeTo::Hand(0),
-                LeftRight::Right => {
-                    watch_pos.x = -watch_pos.x;
-                    watch_rot = watch_rot * Quat::from_rotation_x(PI) * Quat::from_rotation_z(PI);
-                    RelativeTo::Hand(1)
-                }
-            };
-            (watch_pos, watch_rot, relative_to)
+struct ToastData {
+    overlay: OverlayID,
+    dbus_id: Option<u32>,
+}
+
+fn new_toast(
+    toast: Toast,
+    name: Arc<str>,
+    app: &mut AppState,
+) -> Option<(OverlayState, Box<dyn OverlayBackend>, StackedToast)> {
+    let method = toast.display.unwrap_or_else(|| {
+        app.session
+            .toast_topics
+            .get(toast.topic)
+            .copied()
+            .unwrap_or(DisplayMethod::Hide)
+    });
+    let placement = ToastPlacement::new(method, app)?;
+
+    // action buttons are only available in the built-in layout
+    let has_buttons =
+        toast.dbus_id.is_some() && toast.actions.iter().any(|(key, _)| &**key != "default");
+    if let Some(template) = app.session.toast_template.clone() {
+        if !has_buttons {
+            return new_template_toast(&toast, template, name, method, &placement, app);
         }
-    };
+        log::debug!(
+            "{} has no place for action buttons, using the built-in layout",
+            TOAST_TEMPLATE_FILE
+        );
+    }
 
     let title = if toast.title.len() > 0 {
         toast.title
@@ -165,45 +664,320 @@ fn new_toast(toast: Toast, app: &mut AppState) -> Option<(OverlayState, Box<dyn
         (w, h + 20.)
     };
 
+    let header_height = if toast.body.len() > 0 { 30. } else { 0. };
+    let icon = toast.icon.as_ref().and_then(|icon| {
+        let view = icon
+            .upload(app)
+            .map_err(|e| log::warn!("Could not upload toast icon: {:?}", e))
+            .ok()?;
+        Some((view, icon.width as f32, icon.height as f32))
+    });
+    // text goes to the right of the icon
+    let text_offset = if icon.is_some() {
+        ICON_SIZE as f32 + PADDING.0
+    } else {
+        0.
+    };
+    if icon.is_some() {
+        size.0 += text_offset;
+        size.1 = size
+            .1
+            .max(header_height + ICON_SIZE as f32 + PADDING.1 * 2.);
+    }
+
+    // the default action is invoked by clicking the toast itself
+    let buttons: Vec<(Arc<str>, Arc<str>, f32)> = if toast.dbus_id.is_some() {
+        toast
+            .actions
+            .iter()
+            .filter(|(key, _)| &**key != "default")
+            .filter_map(|(key, label)| {
+                let (w, _) = app
+                    .fc
+                    .get_text_size(label, FONT_SIZE, app.graphics.clone())
+                    .ok()?;
+                Some((key.clone(), label.clone(), w + PADDING.0))
+            })
+            .collect()
+    } else {
+        vec![]
+    };
+    let buttons_width = buttons.iter().map(|b| b.2 + ACTION_GAP).sum::<f32>() - ACTION_GAP;
+
+    let content_height = size.1;
+    if !buttons.is_empty() {
+        size.0 = size.0.max(buttons_width);
+        size.1 += ACTION_BUTTON_HEIGHT + PADDING.1;
+    }
+
     let og_width = size.0;
     size.0 += PADDING.0 * 2.;
 
-    let mut canvas = CanvasBuilder::<(), ()>::new(
+    let (state, stacked) = placement.overlay_state(
+        name,
+        method,
+        size.0 * PIXELS_TO_METERS,
+        size.1 * PIXELS_TO_METERS,
+    );
+
+    let mut canvas = CanvasBuilder::<ToastData, Arc<str>>::new(
         size.0 as _,
         size.1 as _,
         app.graphics.clone(),
         app.graphics.native_format,
-        (),
+        ToastData {
+            overlay: state.id,
+            dbus_id: toast.dbus_id,
+        },
     )
     .ok()?;
 
+    let theme = Theme::from_config(&app.session.config);
     canvas.font_size = FONT_SIZE;
-    canvas.fg_color = color_parse("#cad3f5").unwrap(); // want panic
-    canvas.bg_color = color_parse("#1e2030").unwrap(); // want panic
-    canvas.panel(0., 0., size.0, size.1, 16.);
+    canvas.fg_color = theme.parse_or_default("$fg");
+    canvas.bg_color = theme.parse_or_default("$bg_alt");
+
+    // clicking the toast dismisses it, invoking the default action if any
+    let button = canvas.button(0., 0., size.0, size.1, 16., "".into());
+    button.state = toast
+        .actions
+        .iter()
+        .find(|(key, _)| &**key == "default")
+        .map(|(key, _)| key.clone());
+    button.on_press = Some(toast_on_press);
+
+    if let Some((view, w, h)) = icon {
+        let x = PADDING.0 + (ICON_SIZE as f32 - w) * 0.5;
+        let y = header_height + (content_height - header_height - h) * 0.5;
+        let sprite = canvas.sprite(x, y, w, h);
+        sprite.fg_color = Vec4::ONE;
+        sprite.set_sprite(view);
+    }
 
     if toast.body.len() > 0 {
-        canvas.label(PADDING.0, 54., og_width, size.1 - 54., 3., toast.body);
+        canvas.label(
+            PADDING.0 + text_offset,
+            54.,
+            og_width - text_offset,
+            content_height - 54.,
+            3.,
+            toast.body,
+        );
+    }
 
-        canvas.fg_color = color_parse("#b8c0e0").unwrap(); // want panic
-        canvas.bg_color = color_parse("#24273a").unwrap(); // want panic
+    if !buttons.is_empty() {
+        canvas.bg_color = theme.parse_or_default("$surface_dim");
+        let mut x = (size.0 - buttons_width) * 0.5;
+        let y = size.1 - ACTION_BUTTON_HEIGHT - PADDING.1;
+        for (key, label, w) in buttons {
+            let button = canvas.button(x, y, w, ACTION_BUTTON_HEIGHT, 8., label);
+            button.state = Some(key);
+            button.on_press = Some(toast_on_press);
+            x += w + ACTION_GAP;
+        }
+    }
+
+    if toast.body.len() > 0 {
+        canvas.fg_color = theme.parse_or_default("$fg_dim");
+        canvas.bg_color = theme.parse_or_default("$bg");
         canvas.panel(0., 0., size.0, 30., 16.);
         canvas.label_centered(PADDING.0, 16., og_width, FONT_SIZE as f32 + 2., 16., title);
     } else {
-        canvas.label_centered(PADDING.0, 0., og_width, size.1, 16., title);
-    }
-
-    let state = OverlayState {
-        name: TOAST_NAME.clone(),
-        want_visible: true,
-        spawn_scale: size.0 * PIXELS_TO_METERS,
-        spawn_rotation,
-        spawn_point,
-        z_order: 70,
-        relative_to,
-        ..Default::default()
-    };
+        canvas.label_centered(
+            PADDING.0 + text_offset,
+            0.,
+            og_width - text_offset,
+            content_height,
+            16.,
+            title,
+        );
+    }
+
     let backend = Box::new(canvas.build());
 
-    Some((state, backend))
+    Some((state, backend, stacked))
+}
+
+/// toast.yaml with its components expanded, None if there is none
+pub fn try_load_toast_template() -> anyhow::Result<Option<ModularUiConfig>> {
+    let Some(yaml) = config_io::load(TOAST_TEMPLATE_FILE) else {
+        return Ok(None);
+    };
+    let mut template: ModularUiConfig = config_check::parse_yaml(TOAST_TEMPLATE_FILE, &yaml)?;
+    // placeholders can be inside of components
+    template.elements = expand_elements(&template)?;
+    template.include.clear();
+    template.components.clear();
+    Ok(Some(template))
+}
+
+pub fn load_toast_template() -> Option<ModularUiConfig> {
+    // not reported as a toast, that would fail the same way
+    try_load_toast_template()
+        .map_err(|e| {
+            log::error!(
+                "Failed to load {}: {:?}, using the built-in layout",
+                TOAST_TEMPLATE_FILE,
+                e
+            )
+        })
+        .ok()
+        .flatten()
+}
+
+fn new_template_toast(
+    toast: &Toast,
+    mut template: ModularUiConfig,
+    name: Arc<str>,
+    method: DisplayMethod,
+    placement: &ToastPlacement,
+    app: &mut AppState,
+) -> Option<(OverlayState, Box<dyn OverlayBackend>, StackedToast)> {
+    // the Sprite element looks up app.sprites before trying to load a file
+    let icon_key: Arc<str> = format!("{}-icon", name).into();
+    let icon = toast.icon.as_ref().and_then(|icon| {
+        let view = icon
+            .upload(app)
+            .map_err(|e| log::warn!("Could not upload toast icon: {:?}", e))
+            .ok()?;
+        app.sprites.arc_set(icon_key.clone(), view);
+        Some(icon_key.clone())
+    });
+
+    fill_toast_template(&mut template.elements, toast, icon.as_ref());
+    let canvas = modular_canvas(&template, app);
+    app.sprites.arc_rm(&icon_key);
+
+    let canvas = canvas
+        .map_err(|e| {
+            log::error!(
+                "Failed to build toast from {}: {:?}",
+                TOAST_TEMPLATE_FILE,
+                e
+            )
+        })
+        .ok()?;
+
+    let height = template.width * template.size[1] as f32 / template.size[0] as f32;
+    let (state, stacked) = placement.overlay_state(name, method, template.width, height);
+
+    let interaction = TemplateToastInteraction {
+        data: ToastData {
+            overlay: state.id,
+            dbus_id: toast.dbus_id,
+        },
+        default_action: toast
+            .actions
+            .iter()
+            .find(|(key, _)| &**key == "default")
+            .map(|(key, _)| key.clone()),
+    };
+    let backend = Box::new(SplitOverlayBackend {
+        renderer: Box::new(canvas),
+        interaction: Box::new(interaction),
+    });
+
+    Some((state, backend, stacked))
+}
+
+/// Clicking anywhere on a template toast acts like clicking a built-in one
+struct TemplateToastInteraction {
+    data: ToastData,
+    default_action: Option<Arc<str>>,
+}
+
+impl InteractionHandler for TemplateToastInteraction {
+    fn on_hover(&mut self, _app: &mut AppState, _hit: &PointerHit) -> Option<Haptics> {
+        None
+    }
+    fn on_left(&mut self, _app: &mut AppState, _pointer: usize) {}
+    fn on_pointer(&mut self, app: &mut AppState, _hit: &PointerHit, pressed: bool) {
+        if pressed {
+            toast_pressed(&self.data, self.default_action.as_ref(), app);
+        }
+    }
+    fn on_scroll(&mut self, _app: &mut AppState, _hit: &PointerHit, _delta: f32) {}
+}
+
+/// Replaces the toast placeholders with static labels and sprites
+fn fill_toast_template(elements: &mut [ModularElement], toast: &Toast, icon: Option<&Arc<str>>) {
+    for elem in elements.iter_mut() {
+        let icon_rect = match elem {
+            ModularElement::Label {
+                rect,
+                data: LabelContent::ToastIcon,
+                ..
+            }
+            | ModularElement::CenteredLabel {
+                rect,
+                data: LabelContent::ToastIcon,
+                ..
+            } => Some(*rect),
+            _ => None,
+        };
+        if let (Some(rect), Some(sprite)) = (icon_rect, icon) {
+            *elem = ModularElement::Sprite {
+                rect,
+                sprite: sprite.clone(),
+                sprite_st: None,
+            };
+            continue;
+        }
+
+        let (ModularElement::Label { data, .. } | ModularElement::CenteredLabel { data, .. }) =
+            elem
+        else {
+            continue;
+        };
+        let text = match data {
+            LabelContent::ToastTitle => toast.title.clone(),
+            LabelContent::ToastBody => toast.body.clone(),
+            LabelContent::ToastTopic => toast.topic.label().into(),
+            LabelContent::ToastIcon => "".into(),
+            _ => continue,
+        };
+        *data = LabelContent::Static { text };
+    }
+}
+
+fn toast_on_press(
+    control: &mut Control<ToastData, Arc<str>>,
+    data: &mut ToastData,
+    app: &mut AppState,
+    _mode: PointerMode,
+) {
+    toast_pressed(data, control.state.as_ref(), app);
+}
+
+/// Dismisses the toast, invoking the given action if we're the notification server
+fn toast_pressed(data: &ToastData, action: Option<&Arc<str>>, app: &mut AppState) {
+    if let (Some(id), Some(key)) = (data.dbus_id, action) {
+        if is_reply_action(key) {
+            show_keyboard(app);
+        }
+        app.served_notifications.invoke_action(id, key.clone());
+    }
+    dismiss_toast(app, data.overlay, CloseReason::Dismissed);
+}
+
+/// Actions that expect text input from the user. Labels are translated, so only the key is used,
+/// such as the "inline-reply" of KDE and GNOME.
+fn is_reply_action(key: &str) -> bool {
+    key.to_ascii_lowercase().contains("reply")
+}
+
+fn show_keyboard(app: &mut AppState) {
+    app.tasks.enqueue(TaskType::Overlay(
+        OverlaySelector::Name(KEYBOARD_NAME.into()),
+        Box::new(|app, o| {
+            if o.want_visible {
+                return;
+            }
+            o.want_visible = true;
+            if o.recenter {
+                o.show_hide = true;
+                o.reset(app, false);
+            }
+        }),
+    ));
 }
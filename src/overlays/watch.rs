@@ -44,7 +44,7 @@ pub fn create_watch_canvas(
 ) -> anyhow::Result<Canvas<(), ModularData>> {
     let config = config.unwrap_or_else(|| load_known_yaml::<ModularUiConfig>(ConfigType::Watch));
 
-    modular_canvas(&config.size, &config.elements, state)
+    modular_canvas(&config, state)
 }
 
 pub fn watch_fade<D>(app: &mut AppState, watch: &mut OverlayData<D>)
@@ -1,4 +1,5 @@
 use glam::{vec3a, Affine2, Vec3, Vec3A};
+use schemars::JsonSchema;
 use serde::Deserialize;
 use std::{cell::RefCell, collections::HashMap, rc::Rc, sync::Arc};
 use vulkano::image::SubresourceLayout;
@@ -424,13 +425,13 @@ where
     })
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Clone, JsonSchema)]
 pub enum WayVRDisplayClickAction {
     ToggleVisibility,
     Reset,
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Clone, JsonSchema)]
 pub enum WayVRAction {
     AppClick {
         catalog_name: Arc<str>,
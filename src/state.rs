@@ -2,6 +2,7 @@ use anyhow::bail;
 use glam::Affine3A;
 use idmap::IdMap;
 use rodio::{Decoder, OutputStream, OutputStreamHandle, Source};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use smallvec::{smallvec, SmallVec};
 use std::{io::Cursor, sync::Arc};
@@ -11,17 +12,28 @@ use vulkano::image::view::ImageView;
 use {
     crate::config_wayvr::{self, WayVRConfig},
     crate::overlays::wayvr::WayVRState,
-    std::{cell::RefCell, rc::Rc},
+    std::cell::RefCell,
 };
 
+#[cfg(feature = "osc")]
+use crate::backend::osc::OscSender;
+
+#[cfg(any(feature = "wayvr", feature = "osc"))]
+use std::rc::Rc;
+
 use crate::{
-    backend::{input::InputState, overlay::OverlayID, task::TaskContainer},
+    backend::{
+        input::InputState, notifications::ServedNotifications, overlay::OverlayID,
+        task::TaskContainer, tts::TextToSpeech,
+    },
     config::{AStrMap, GeneralConfig},
     config_io,
     graphics::WlxGraphics,
-    gui::font::FontCache,
+    gui::{font::FontCache, modular::ModularUiConfig},
     hid::HidProvider,
-    overlays::toast::{DisplayMethod, ToastTopic},
+    overlays::toast::{
+        load_toast_template, DisplayMethod, ToastHistory, ToastRule, ToastStack, ToastTopic,
+    },
     shaders::{
         frag_color, frag_glyph, frag_grid, frag_screen, frag_sprite, frag_sprite2, frag_sprite2_hl,
         frag_swapchain, vert_common,
@@ -48,9 +60,17 @@ pub struct AppState {
     pub anchor: Affine3A,
     pub sprites: AStrMap<Arc<ImageView>>,
     pub keyboard_focus: KeyboardFocus,
+    pub toast_history: ToastHistory,
+    pub served_notifications: ServedNotifications,
+    pub toast_stack: ToastStack,
+    pub tts: TextToSpeech,
 
     #[cfg(feature = "wayvr")]
     pub wayvr: Option<Rc<RefCell<WayVRState>>>, // Dynamically created if requested
+
+    /// Shared by the backend and the keyboard chatbox
+    #[cfg(feature = "osc")]
+    pub osc_sender: Option<Rc<OscSender>>,
 }
 
 impl AppState {
@@ -102,6 +122,12 @@ impl AppState {
             .wayvr_config
             .post_load(&session.config, &mut tasks)?;
 
+        #[cfg(feature = "osc")]
+        let osc_sender = OscSender::new(session.config.osc_out_port)
+            .map_err(|e| log::error!("{}", e))
+            .ok()
+            .map(Rc::new);
+
         Ok(AppState {
             fc: FontCache::new(session.config.primary_font.clone())?,
             session,
@@ -114,9 +140,16 @@ impl AppState {
             anchor: Affine3A::IDENTITY,
             sprites: AStrMap::new(),
             keyboard_focus: KeyboardFocus::PhysicalScreen,
+            toast_history: ToastHistory::default(),
+            served_notifications: ServedNotifications::default(),
+            toast_stack: ToastStack::default(),
+            tts: TextToSpeech::default(),
 
             #[cfg(feature = "wayvr")]
             wayvr,
+
+            #[cfg(feature = "osc")]
+            osc_sender,
         })
     }
 
@@ -142,6 +175,9 @@ pub struct AppSession {
     pub wayvr_config: WayVRConfig,
 
     pub toast_topics: IdMap<ToastTopic, DisplayMethod>,
+    pub toast_rules: Vec<ToastRule>,
+    /// Parsed toast.yaml, if any
+    pub toast_template: Option<ModularUiConfig>,
 }
 
 impl AppSession {
@@ -150,24 +186,34 @@ impl AppSession {
         log::info!("Config root path: {}", config_root_path.to_string_lossy());
         let config = GeneralConfig::load_from_disk();
 
+        #[cfg(feature = "wayvr")]
+        let wayvr_config = config_wayvr::load_wayvr();
+
+        let mut session = AppSession {
+            config,
+            toast_topics: IdMap::new(),
+            toast_rules: vec![],
+            toast_template: load_toast_template(),
+            #[cfg(feature = "wayvr")]
+            wayvr_config,
+        };
+        session.load_toast_rules();
+        session
+    }
+
+    /// Rebuilds toast topics and rules from the current config.
+    pub fn load_toast_rules(&mut self) {
         let mut toast_topics = IdMap::new();
         toast_topics.insert(ToastTopic::System, DisplayMethod::Center);
         toast_topics.insert(ToastTopic::DesktopNotification, DisplayMethod::Center);
         toast_topics.insert(ToastTopic::XSNotification, DisplayMethod::Center);
 
-        config.notification_topics.iter().for_each(|(k, v)| {
+        self.config.notification_topics.iter().for_each(|(k, v)| {
             toast_topics.insert(*k, *v);
         });
 
-        #[cfg(feature = "wayvr")]
-        let wayvr_config = config_wayvr::load_wayvr();
-
-        AppSession {
-            config,
-            toast_topics,
-            #[cfg(feature = "wayvr")]
-            wayvr_config,
-        }
+        self.toast_topics = toast_topics;
+        self.toast_rules = ToastRule::compile_all(&self.config.notification_rules);
     }
 }
 
@@ -219,7 +265,7 @@ pub struct ScreenMeta {
     pub native_handle: u32,
 }
 
-#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+#[derive(Serialize, Deserialize, Clone, Copy, Default, JsonSchema)]
 #[repr(u8)]
 pub enum LeftRight {
     #[default]